@@ -1,7 +1,17 @@
+mod number_words;
+mod report;
+mod scanner;
+mod strategy;
+
 use clap::{App, Arg};
-use std::fs;
-use std::collections::HashMap;
-use regex::Regex;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::process;
+use number_words::NumberWords;
+use report::{LineReport, ReportFormat, ReportWriter};
+use scanner::{first_and_last_of, NumberScanner};
+use strategy::{CalibrationError, ExtractionMode, ExtractionStrategy};
+
 fn main() {
     let matches = App::new("Calibration Summarizer")
         .version("1.0")
@@ -9,115 +19,150 @@ fn main() {
         .about("Calculates the sum of calibration values from a file")
         .arg(
             Arg::with_name("file")
-                .help("The file to process")
-                .required(true)
+                .help("The file to process, reads stdin when omitted or '-'")
                 .index(1),
         )
+        .arg(
+            Arg::with_name("mode")
+                .short('m')
+                .long("mode")
+                .value_name("MODE")
+                .help("How a line becomes a value: first/last digit, first/last digit or word, all digits concatenated, or sum of all numbers")
+                .takes_value(true)
+                .possible_values(["digits", "words", "concat", "sum"])
+                .default_value("words"),
+        )
+        .arg(
+            Arg::with_name("report")
+                .long("report")
+                .value_name("FORMAT")
+                .help("Print a per-line report instead of the sum")
+                .takes_value(true)
+                .possible_values(["csv", "json"]),
+        )
+        .arg(
+            Arg::with_name("words")
+                .long("words")
+                .value_name("CONFIG")
+                .help("JSON file with number-word tables per locale")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("locale")
+                .long("locale")
+                .value_name("LOCALE")
+                .help("Locale to load from the words config")
+                .takes_value(true)
+                .default_value("en")
+                .requires("words"),
+        )
         .get_matches();
 
-    let file_name = matches.value_of("file").unwrap();
-    let lines = read_lines_from_file(file_name);
-    let sum = sum_calibration_values(lines);
-
-    println!("The sum of the calibration values is: {}", sum);
-}
-
+    let number_words = match matches.value_of("words") {
+        Some(config) => {
+            let locale = matches.value_of("locale").unwrap();
+            NumberWords::from_config_file(config, locale).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                process::exit(1);
+            })
+        }
+        None => NumberWords::english(),
+    };
+    let mode: ExtractionMode = matches.value_of("mode").unwrap().parse().unwrap();
+    let strategy = mode.strategy(&number_words);
+    let scanner = if mode.uses_words() {
+        NumberScanner::new(&number_words)
+    } else {
+        NumberScanner::new(&NumberWords::new(vec![]))
+    };
 
-fn read_lines_from_file(file_path: &str) -> Vec<String> {
-    fs::read_to_string(file_path)
-        .expect("Failed to read file")
-        .lines()
-        .map(|line| line.to_string())
-        .collect()
-}
+    let input = open_input(matches.value_of("file")).unwrap_or_else(|e| {
+        eprintln!("Failed to open input: {}", e);
+        process::exit(1);
+    });
 
-fn sum_calibration_values(lines: Vec<String>) -> usize {
-    lines.iter()
-        .map(|line| extract_calibration_value(line))
-        .sum()
-}
+    let result = match matches.value_of("report") {
+        Some(format) => {
+            let format = if format == "json" { ReportFormat::Json } else { ReportFormat::Csv };
+            write_report(input, &scanner, strategy.as_ref(), format)
+        }
+        None => sum_calibration_values(input, strategy.as_ref()).map(|summary| {
+            for (line_number, error) in &summary.errors {
+                eprintln!("line {}: {}", line_number, error);
+            }
+            println!("The sum of the calibration values is: {}", summary.sum);
+            if !summary.errors.is_empty() {
+                eprintln!("{} lines could not be calibrated", summary.errors.len());
+                process::exit(1);
+            }
+        }),
+    };
 
-fn extract_calibration_value(input: &str) -> usize {
-    let first_number = find_first_number(input).unwrap_or(0);
-    let last_number = find_last_number(input).unwrap_or(0);
-    first_number * 10 + last_number
+    if let Err(e) = result {
+        eprintln!("Failed to process input: {}", e);
+        process::exit(1);
+    }
 }
 
 
-fn find_first_spelled_out_number(input: &str) -> Option<(usize, usize)> {
-    let words_to_numbers = [
-        ("one", 1), ("two", 2), ("three", 3), ("four", 4),
-        ("five", 5), ("six", 6), ("seven", 7), ("eight", 8), ("nine", 9),
-    ];
-
-    words_to_numbers
-        .iter()
-        .filter_map(|(word, number)| {
-            input.find(word).map(|index| (index, *number))
-        })
-        .min_by_key(|&(index, _)| index)
+// Sum of the lines that produced a value, and the lines that did not
+#[derive(Debug, Default, PartialEq)]
+struct CalibrationSummary {
+    sum: usize,
+    errors: Vec<(usize, CalibrationError)>,
 }
 
-fn find_first_digit(input: &str) -> Option<(usize, usize)> {
-    input.char_indices()
-        .filter_map(|(index, ch)| {
-            ch.to_digit(10)
-                .map(|digit| (index, digit as usize))
-        })
-        .next()
-}
-
-fn find_first_number(input: &str) -> Option<usize> {
-    let spelled_out = find_first_spelled_out_number(input);
-    let digit = find_first_digit(input);
-
-    [spelled_out, digit]
-        .iter()
-        .filter_map(|&option| option)
-        .min_by_key(|&(index, _)| index)
-        .map(|(_, number)| number)
+fn open_input(file_name: Option<&str>) -> io::Result<Box<dyn BufRead>> {
+    match file_name {
+        None | Some("-") => Ok(Box::new(BufReader::new(io::stdin()))),
+        Some(path) => Ok(Box::new(BufReader::new(File::open(path)?))),
+    }
 }
 
-
-fn find_last_spelled_out_number(input: &str) -> Option<(usize, usize)> {
-    let words_to_numbers = [
-        ("one", 1), ("two", 2), ("three", 3), ("four", 4),
-        ("five", 5), ("six", 6), ("seven", 7), ("eight", 8), ("nine", 9),
-    ];
-
-    words_to_numbers
-        .iter()
-        .filter_map(|(word, number)| {
-            input.rfind(word).map(|index| (index, *number))
-        })
-        .max_by_key(|&(index, _)| index)
+fn sum_calibration_values<R: BufRead>(input: R, strategy: &dyn ExtractionStrategy) -> io::Result<CalibrationSummary> {
+    let mut summary = CalibrationSummary::default();
+    for (index, line) in input.lines().enumerate() {
+        match strategy.extract(&line?) {
+            Ok(value) => summary.sum += value,
+            Err(error) => summary.errors.push((index + 1, error)),
+        }
+    }
+    Ok(summary)
 }
 
-fn find_last_digit(input: &str) -> Option<(usize, usize)> {
-    input.char_indices()
-        .rev()
-        .filter_map(|(index, ch)| {
-            ch.to_digit(10)
-                .map(|digit| (index, digit as usize))
-        })
-        .next()
+fn write_report<R: BufRead>(input: R, scanner: &NumberScanner, strategy: &dyn ExtractionStrategy, format: ReportFormat) -> io::Result<()> {
+    let mut writer = ReportWriter::new(io::stdout().lock(), format);
+    for (index, line) in input.lines().enumerate() {
+        let report = report_line(index + 1, &line?, scanner, strategy);
+        writer.write_row(&report)?;
+    }
+    writer.finish().map(|_| ())
 }
 
-fn find_last_number(input: &str) -> Option<usize> {
-    let spelled_out = find_last_spelled_out_number(input);
-    let digit = find_last_digit(input);
+fn report_line(line_number: usize, input: &str, scanner: &NumberScanner, strategy: &dyn ExtractionStrategy) -> LineReport {
+    // One scan of the line serves the report and the value
+    let matches = scanner.matches(input);
+    let (first, last) = match first_and_last_of(&matches) {
+        Some((first, last)) => (Some(first), Some(last)),
+        None => (None, None),
+    };
+    let value = strategy.extract_from_matches(&matches);
 
-    [spelled_out, digit]
-        .iter()
-        .filter_map(|&option| option)
-        .max_by_key(|&(index, _)| index)
-        .map(|(_, number)| number)
+    LineReport {
+        line_number,
+        first,
+        last,
+        matches,
+        calibration_value: value.as_ref().ok().copied(),
+        error: value.err().map(|e| e.to_string()),
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use scanner::NumberMatch;
 
     #[test]
     fn test_extract_calibration_value_with_words() {
@@ -132,50 +177,109 @@ mod tests {
         ];
 
         for (line, expected) in examples {
-            assert_eq!(extract_calibration_value(line), expected, "Failed on line: {}", line);
+            assert_eq!(words_strategy().extract(line), Ok(expected), "Failed on line: {}", line);
         }
     }
 
+    fn words_strategy() -> Box<dyn ExtractionStrategy> {
+        ExtractionMode::DigitsAndWords.strategy(&NumberWords::english())
+    }
+
+    fn english_scanner() -> NumberScanner {
+        NumberScanner::new(&NumberWords::english())
+    }
+
+    fn is_word(m: &NumberMatch) -> bool {
+        !m.token.chars().all(|ch| ch.is_ascii_digit())
+    }
+
     #[test]
     fn test_find_first_spelled_out_number() {
         let input = "h1twofour434";
-        let expected = Some((2, 2)); // "two" starts at index 3
-        assert_eq!(find_first_spelled_out_number(input), expected);
+        let expected = Some(NumberMatch::new(2, 2, "two")); // "two" starts at index 2
+        assert_eq!(english_scanner().matches(input).into_iter().find(is_word), expected);
     }
 
     #[test]
     fn test_find_first_digit() {
         let input = "h1twofour434";
-        let expected = Some((1, 1)); // "1" is at index 1
-        assert_eq!(find_first_digit(input), expected);
+        let expected = Some(NumberMatch::new(1, 1, "1")); // "1" is at index 1
+        assert_eq!(english_scanner().matches(input).into_iter().find(|m| !is_word(m)), expected);
     }
 
     #[test]
     fn test_find_first_number() {
         let input = "h1twofour434";
         let expected = Some(1); // "1" is the first number
-        assert_eq!(find_first_number(input), expected);
+        assert_eq!(english_scanner().first_and_last(input).map(|(first, _)| first.value), expected);
     }
 
     #[test]
     fn test_find_last_spelled_out_number() {
         let input = "ftwofdjsif7eight";
-        let expected = Some((11, 8)); // "eight" starts at index 11
-        assert_eq!(find_last_spelled_out_number(input), expected);
+        let expected = Some(NumberMatch::new(11, 8, "eight")); // "eight" starts at index 11
+        assert_eq!(english_scanner().matches(input).into_iter().rev().find(is_word), expected);
     }
 
     #[test]
     fn test_find_last_digit() {
         let input = "ftwofdjsif7eight";
-        let expected = Some((10, 7)); // "7" is at index 10
-        assert_eq!(find_last_digit(input), expected);
+        let expected = Some(NumberMatch::new(10, 7, "7")); // "7" is at index 10
+        assert_eq!(english_scanner().matches(input).into_iter().rev().find(|m| !is_word(m)), expected);
     }
 
     #[test]
     fn test_find_last_number() {
         let input = "ftwofdjsif7eight";
         let expected = Some(8); // "eight" is the last number
-        assert_eq!(find_last_number(input), expected);
+        assert_eq!(english_scanner().first_and_last(input).map(|(_, last)| last.value), expected);
+    }
+
+    #[test]
+    fn test_sum_calibration_values_from_reader() {
+        let input = "1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet\n";
+        let summary = sum_calibration_values(input.as_bytes(), words_strategy().as_ref()).unwrap();
+        assert_eq!(summary, CalibrationSummary { sum: 142, errors: vec![] });
+    }
+
+    #[test]
+    fn test_lines_without_digits_are_reported() {
+        let input = "1abc2\nnothing here\ntreb7uchet\n";
+        let summary = sum_calibration_values(input.as_bytes(), words_strategy().as_ref()).unwrap();
+        assert_eq!(summary, CalibrationSummary { sum: 89, errors: vec![(2, CalibrationError::NoDigits)] });
+    }
+
+    #[test]
+    fn test_sum_with_digits_only_mode() {
+        let input = "two1nine\n4nineeightseven2\n";
+        let strategy = ExtractionMode::Digits.strategy(&NumberWords::english());
+        let summary = sum_calibration_values(input.as_bytes(), strategy.as_ref()).unwrap();
+        assert_eq!(summary.sum, 11 + 42);
+    }
+
+    #[test]
+    fn test_report_line_records_matched_tokens() {
+        let report = report_line(3, "xtwone3four", &english_scanner(), words_strategy().as_ref());
+        assert_eq!(report, LineReport {
+            line_number: 3,
+            first: Some(NumberMatch::new(1, 2, "two")),
+            last: Some(NumberMatch::new(7, 4, "four")),
+            matches: vec![
+                NumberMatch::new(1, 2, "two"),
+                NumberMatch::new(3, 1, "one"),
+                NumberMatch::new(6, 3, "3"),
+                NumberMatch::new(7, 4, "four"),
+            ],
+            calibration_value: Some(24),
+            error: None,
+        });
+    }
+
+    #[test]
+    fn test_extract_calibration_value_with_other_locale() {
+        let strategy = ExtractionMode::DigitsAndWords.strategy(&NumberWords::new(vec![("un", 1), ("deux", 2), ("neuf", 9)]));
+        assert_eq!(strategy.extract("deux1neuf"), Ok(29));
+        assert_eq!(strategy.extract("two1nine"), Ok(11));
     }
 
 
@@ -0,0 +1,108 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+
+// Table of spelled-out words and the digit each one stands for
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberWords {
+    words: Vec<(String, usize)>,
+}
+
+#[derive(Debug)]
+pub enum NumberWordsError {
+    Io(String),
+    InvalidConfig(String),
+    UnknownLocale(String),
+    InvalidDigit(String, usize),
+}
+
+impl fmt::Display for NumberWordsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NumberWordsError::Io(message) => write!(f, "could not read word config: {}", message),
+            NumberWordsError::InvalidConfig(message) => write!(f, "invalid word config: {}", message),
+            NumberWordsError::UnknownLocale(locale) => write!(f, "locale '{}' not found in word config", locale),
+            NumberWordsError::InvalidDigit(word, value) => {
+                write!(f, "word '{}' maps to {}, expected a single digit", word, value)
+            }
+        }
+    }
+}
+
+impl NumberWords {
+    pub fn english() -> Self {
+        NumberWords::new(vec![
+            ("one", 1), ("two", 2), ("three", 3), ("four", 4),
+            ("five", 5), ("six", 6), ("seven", 7), ("eight", 8), ("nine", 9),
+        ])
+    }
+
+    pub fn new(words: Vec<(&str, usize)>) -> Self {
+        NumberWords {
+            words: words.into_iter().map(|(word, number)| (word.to_string(), number)).collect(),
+        }
+    }
+
+    // Loads the table for `locale` from a JSON file of the form
+    // { "fr": { "un": 1, "deux": 2, ... }, "de": { ... } }
+    pub fn from_config_file(path: &str, locale: &str) -> Result<Self, NumberWordsError> {
+        let contents = fs::read_to_string(path).map_err(|e| NumberWordsError::Io(e.to_string()))?;
+        NumberWords::from_config_str(&contents, locale)
+    }
+
+    pub fn from_config_str(contents: &str, locale: &str) -> Result<Self, NumberWordsError> {
+        let mut locales: HashMap<String, BTreeMap<String, usize>> = serde_json::from_str(contents)
+            .map_err(|e| NumberWordsError::InvalidConfig(e.to_string()))?;
+
+        let table = locales.remove(locale)
+            .ok_or_else(|| NumberWordsError::UnknownLocale(locale.to_string()))?;
+
+        let mut words = Vec::new();
+        for (word, number) in table {
+            if word.is_empty() {
+                return Err(NumberWordsError::InvalidConfig("number words must not be empty".to_string()));
+            }
+            if number > 9 {
+                return Err(NumberWordsError::InvalidDigit(word, number));
+            }
+            words.push((word, number));
+        }
+
+        Ok(NumberWords { words })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.words.iter().map(|(word, number)| (word.as_str(), *number))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+        "fr": { "un": 1, "deux": 2, "trois": 3 },
+        "bad": { "douze": 12 }
+    }"#;
+
+    #[test]
+    fn test_load_locale_from_config() {
+        let words = NumberWords::from_config_str(CONFIG, "fr").unwrap();
+        let mut loaded: Vec<(&str, usize)> = words.iter().collect();
+        loaded.sort();
+        assert_eq!(loaded, vec![("deux", 2), ("trois", 3), ("un", 1)]);
+    }
+
+    #[test]
+    fn test_unknown_locale_is_an_error() {
+        let result = NumberWords::from_config_str(CONFIG, "de");
+        assert!(matches!(result, Err(NumberWordsError::UnknownLocale(_))));
+    }
+
+    #[test]
+    fn test_words_must_map_to_digits() {
+        let result = NumberWords::from_config_str(CONFIG, "bad");
+        assert!(matches!(result, Err(NumberWordsError::InvalidDigit(_, 12))));
+    }
+}
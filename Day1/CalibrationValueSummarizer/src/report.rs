@@ -0,0 +1,180 @@
+use std::io::{self, Write};
+use serde::Serialize;
+use crate::scanner::NumberMatch;
+
+// One row of the per-line report
+#[derive(Debug, PartialEq, Serialize)]
+pub struct LineReport {
+    pub line_number: usize,
+    pub first: Option<NumberMatch>,
+    pub last: Option<NumberMatch>,
+    // Every digit and word in the line, only written to JSON reports
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub matches: Vec<NumberMatch>,
+    pub calibration_value: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+// Writes report rows as they are produced, so the whole input never has to be held in memory
+pub struct ReportWriter<W: Write> {
+    out: W,
+    format: ReportFormat,
+    rows_written: usize,
+}
+
+impl<W: Write> ReportWriter<W> {
+    pub fn new(out: W, format: ReportFormat) -> Self {
+        ReportWriter { out, format, rows_written: 0 }
+    }
+
+    pub fn write_row(&mut self, report: &LineReport) -> io::Result<()> {
+        if self.rows_written == 0 {
+            self.write_header()?;
+        }
+
+        match self.format {
+            ReportFormat::Csv => {
+                let (first_value, first_token, first_offset) = csv_match_fields(&report.first);
+                let (last_value, last_token, last_offset) = csv_match_fields(&report.last);
+                writeln!(
+                    self.out,
+                    "{},{},{},{},{},{},{},{},{}",
+                    report.line_number,
+                    first_value, first_token, first_offset,
+                    last_value, last_token, last_offset,
+                    report.calibration_value.map_or(String::new(), |value| value.to_string()),
+                    report.error.as_deref().map_or(String::new(), csv_escape)
+                )?;
+            }
+            ReportFormat::Json => {
+                if self.rows_written > 0 {
+                    writeln!(self.out, ",")?;
+                }
+                write!(self.out, "  ")?;
+                serde_json::to_writer(&mut self.out, report)?;
+            }
+        }
+
+        self.rows_written += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.rows_written == 0 {
+            self.write_header()?;
+        }
+        if self.format == ReportFormat::Json {
+            if self.rows_written > 0 {
+                writeln!(self.out)?;
+            }
+            writeln!(self.out, "]")?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        match self.format {
+            ReportFormat::Csv => writeln!(
+                self.out,
+                "line,first_value,first_token,first_offset,last_value,last_token,last_offset,calibration_value,error"
+            ),
+            ReportFormat::Json => writeln!(self.out, "["),
+        }
+    }
+}
+
+fn csv_match_fields(number_match: &Option<NumberMatch>) -> (String, String, String) {
+    match number_match {
+        Some(m) => (m.value.to_string(), csv_escape(&m.token), m.offset.to_string()),
+        None => (String::new(), String::new(), String::new()),
+    }
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_report() -> LineReport {
+        LineReport {
+            line_number: 1,
+            first: Some(NumberMatch { offset: 0, value: 2, token: "two".to_string() }),
+            last: Some(NumberMatch { offset: 4, value: 9, token: "nine".to_string() }),
+            matches: vec![
+                NumberMatch { offset: 0, value: 2, token: "two".to_string() },
+                NumberMatch { offset: 3, value: 1, token: "1".to_string() },
+                NumberMatch { offset: 4, value: 9, token: "nine".to_string() },
+            ],
+            calibration_value: Some(29),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_csv_report() {
+        let mut writer = ReportWriter::new(Vec::new(), ReportFormat::Csv);
+        writer.write_row(&sample_report()).unwrap();
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+        assert_eq!(
+            output,
+            "line,first_value,first_token,first_offset,last_value,last_token,last_offset,calibration_value,error\n\
+             1,2,two,0,9,nine,4,29,\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_report_with_error() {
+        let report = LineReport {
+            line_number: 2,
+            first: None,
+            last: None,
+            matches: vec![],
+            calibration_value: None,
+            error: Some("no digits found".to_string()),
+        };
+        let mut writer = ReportWriter::new(Vec::new(), ReportFormat::Csv);
+        writer.write_row(&report).unwrap();
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+        assert_eq!(output.lines().nth(1), Some("2,,,,,,,,no digits found"));
+    }
+
+    #[test]
+    fn test_json_report() {
+        let mut writer = ReportWriter::new(Vec::new(), ReportFormat::Json);
+        writer.write_row(&sample_report()).unwrap();
+        writer.write_row(&sample_report()).unwrap();
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+        let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(parsed.as_array().unwrap().len(), 2);
+        assert_eq!(parsed[0]["first"]["token"], "two");
+        assert_eq!(parsed[0]["last"]["offset"], 4);
+        assert_eq!(parsed[0]["matches"].as_array().unwrap().len(), 3);
+        assert_eq!(parsed[1]["calibration_value"], 29);
+    }
+
+    #[test]
+    fn test_empty_json_report_is_valid() {
+        let writer = ReportWriter::new(Vec::new(), ReportFormat::Json);
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert_eq!(output, "[\n]\n");
+    }
+}
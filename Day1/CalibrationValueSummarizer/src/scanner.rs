@@ -0,0 +1,161 @@
+use aho_corasick::AhoCorasick;
+use serde::Serialize;
+use crate::number_words::NumberWords;
+
+// A digit or spelled-out number found in a line, with the byte offset it starts at
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NumberMatch {
+    pub offset: usize,
+    pub value: usize,
+    pub token: String,
+}
+
+impl NumberMatch {
+    pub fn new(offset: usize, value: usize, token: &str) -> Self {
+        NumberMatch { offset, value, token: token.to_string() }
+    }
+}
+
+// Finds digits and number words in one left-to-right pass over a line.
+// Matches may overlap, so "eightwo" yields both "eight" and "two".
+pub struct NumberScanner {
+    automaton: AhoCorasick,
+    values: Vec<usize>,
+}
+
+impl NumberScanner {
+    pub fn new(words: &NumberWords) -> Self {
+        let mut patterns: Vec<String> = (0..10).map(|digit| digit.to_string()).collect();
+        let mut values: Vec<usize> = (0..10).collect();
+
+        for (word, number) in words.iter() {
+            patterns.push(word.to_string());
+            values.push(number);
+        }
+
+        let automaton = AhoCorasick::new(&patterns).expect("Failed to build number scanner");
+        NumberScanner { automaton, values }
+    }
+
+    // Every match in the line, ordered by the offset it starts at
+    pub fn matches(&self, input: &str) -> Vec<NumberMatch> {
+        let mut matches: Vec<NumberMatch> = self.automaton
+            .find_overlapping_iter(input)
+            .map(|m| NumberMatch::new(m.start(), self.values[m.pattern().as_usize()], &input[m.range()]))
+            .collect();
+        matches.sort_by_key(|m| m.offset);
+        matches
+    }
+
+    // The first and last match of the line, found without collecting the matches in between
+    pub fn first_and_last(&self, input: &str) -> Option<(NumberMatch, NumberMatch)> {
+        let mut first: Option<aho_corasick::Match> = None;
+        let mut last: Option<aho_corasick::Match> = None;
+
+        for m in self.automaton.find_overlapping_iter(input) {
+            if first.is_none_or(|f| (m.start(), f.len()) < (f.start(), m.len())) {
+                first = Some(m);
+            }
+            if last.is_none_or(|l| (m.start(), m.len()) > (l.start(), l.len())) {
+                last = Some(m);
+            }
+        }
+
+        let to_number_match = |m: aho_corasick::Match| {
+            NumberMatch::new(m.start(), self.values[m.pattern().as_usize()], &input[m.range()])
+        };
+        Some((to_number_match(first?), to_number_match(last?)))
+    }
+}
+
+// The first and last of matches already found in a line, picked the same way as
+// `NumberScanner::first_and_last`: the longest match wins when two start at the same offset
+pub fn first_and_last_of(matches: &[NumberMatch]) -> Option<(NumberMatch, NumberMatch)> {
+    let first = matches.iter().min_by_key(|m| (m.offset, std::cmp::Reverse(m.token.len())))?;
+    let last = matches.iter().max_by_key(|m| (m.offset, m.token.len()))?;
+    Some((first.clone(), last.clone()))
+}
+
+// Runs of adjacent digit matches, each read as one number's digits
+pub fn digit_runs(matches: &[NumberMatch]) -> Vec<String> {
+    let mut runs: Vec<String> = vec![];
+    let mut run_end = None;
+    for m in matches.iter().filter(|m| m.token.len() == 1 && m.token.as_bytes()[0].is_ascii_digit()) {
+        match runs.last_mut() {
+            Some(run) if run_end == Some(m.offset) => run.push_str(&m.token),
+            _ => runs.push(m.token.clone()),
+        }
+        run_end = Some(m.offset + 1);
+    }
+    runs
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // Reference implementation: try every pattern at every offset
+    fn naive_matches(input: &str, words: &NumberWords) -> Vec<NumberMatch> {
+        let mut matches = Vec::new();
+        for (offset, ch) in input.char_indices() {
+            if let Some(digit) = ch.to_digit(10) {
+                matches.push(NumberMatch::new(offset, digit as usize, &input[offset..offset + 1]));
+            }
+            for (word, number) in words.iter() {
+                if input[offset..].starts_with(word) {
+                    matches.push(NumberMatch::new(offset, number, word));
+                }
+            }
+        }
+        matches
+    }
+
+    #[test]
+    fn test_overlapping_words_are_all_found() {
+        let scanner = NumberScanner::new(&NumberWords::english());
+        let values: Vec<usize> = scanner.matches("eightwone").iter().map(|m| m.value).collect();
+        assert_eq!(values, vec![8, 2, 1]);
+    }
+
+    #[test]
+    fn test_first_and_last_with_overlap() {
+        let scanner = NumberScanner::new(&NumberWords::english());
+        let (first, last) = scanner.first_and_last("7twone").unwrap();
+        assert_eq!(first, NumberMatch::new(0, 7, "7"));
+        assert_eq!(last, NumberMatch::new(3, 1, "one"));
+    }
+
+    #[test]
+    fn test_first_and_last_of_matches_agrees_with_scanner() {
+        let scanner = NumberScanner::new(&NumberWords::english());
+        for line in ["7twone", "eightwo", "oneight", "abc", "x1y"] {
+            assert_eq!(first_and_last_of(&scanner.matches(line)), scanner.first_and_last(line), "Failed on line: {}", line);
+        }
+    }
+
+    #[test]
+    fn test_digit_runs() {
+        let scanner = NumberScanner::new(&NumberWords::english());
+        assert_eq!(digit_runs(&scanner.matches("12one34x5")), vec!["12", "34", "5"]);
+    }
+
+    #[test]
+    fn test_line_without_numbers() {
+        let scanner = NumberScanner::new(&NumberWords::english());
+        assert_eq!(scanner.first_and_last("abcdef"), None);
+        assert!(scanner.matches("abcdef").is_empty());
+    }
+
+    #[test]
+    fn test_matches_agree_with_naive_scan_on_puzzle_input() {
+        let words = NumberWords::english();
+        let scanner = NumberScanner::new(&words);
+        let contents = fs::read_to_string("puzzle_input.txt").unwrap();
+
+        for line in contents.lines() {
+            assert_eq!(scanner.matches(line), naive_matches(line, &words), "Failed on line: {}", line);
+        }
+    }
+}
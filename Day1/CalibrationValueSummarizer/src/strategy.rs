@@ -0,0 +1,220 @@
+use std::fmt;
+use std::str::FromStr;
+use regex::Regex;
+use crate::number_words::NumberWords;
+use crate::scanner::{digit_runs, first_and_last_of, NumberMatch, NumberScanner};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalibrationError {
+    NoDigits,
+    Overflow(String),
+}
+
+impl fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CalibrationError::NoDigits => write!(f, "no digits found"),
+            CalibrationError::Overflow(number) => write!(f, "value {} is too large", number),
+        }
+    }
+}
+
+// A rule for turning one line of the calibration document into a value
+pub trait ExtractionStrategy {
+    fn extract(&self, line: &str) -> Result<usize, CalibrationError>;
+
+    // The same value from the matches a NumberScanner with the same words already found in
+    // the line, so a report doesn't scan each line again
+    fn extract_from_matches(&self, matches: &[NumberMatch]) -> Result<usize, CalibrationError>;
+}
+
+// First number × 10 + last number, with or without spelled-out words
+pub struct FirstAndLast {
+    scanner: NumberScanner,
+}
+
+impl FirstAndLast {
+    pub fn digits() -> Self {
+        FirstAndLast { scanner: NumberScanner::new(&NumberWords::new(vec![])) }
+    }
+
+    pub fn digits_and_words(words: &NumberWords) -> Self {
+        FirstAndLast { scanner: NumberScanner::new(words) }
+    }
+}
+
+impl ExtractionStrategy for FirstAndLast {
+    fn extract(&self, line: &str) -> Result<usize, CalibrationError> {
+        self.scanner.first_and_last(line)
+            .map(|(first, last)| first.value * 10 + last.value)
+            .ok_or(CalibrationError::NoDigits)
+    }
+
+    fn extract_from_matches(&self, matches: &[NumberMatch]) -> Result<usize, CalibrationError> {
+        first_and_last_of(matches)
+            .map(|(first, last)| first.value * 10 + last.value)
+            .ok_or(CalibrationError::NoDigits)
+    }
+}
+
+// Every digit in the line read as one number, so "a1b2c3" is 123
+pub struct ConcatenatedDigits;
+
+impl ExtractionStrategy for ConcatenatedDigits {
+    fn extract(&self, line: &str) -> Result<usize, CalibrationError> {
+        let digits: String = line.chars().filter(|ch| ch.is_ascii_digit()).collect();
+        concatenated_value(digits)
+    }
+
+    fn extract_from_matches(&self, matches: &[NumberMatch]) -> Result<usize, CalibrationError> {
+        concatenated_value(digit_runs(matches).concat())
+    }
+}
+
+fn concatenated_value(digits: String) -> Result<usize, CalibrationError> {
+    if digits.is_empty() {
+        return Err(CalibrationError::NoDigits);
+    }
+    digits.parse::<usize>().map_err(|_| CalibrationError::Overflow(digits))
+}
+
+// Sum of every run of digits in the line, so "12ab34" is 46
+pub struct SumOfNumbers {
+    number: Regex,
+}
+
+impl SumOfNumbers {
+    pub fn new() -> Self {
+        // Not \d, which would also match digits from other scripts that usize can't parse
+        SumOfNumbers { number: Regex::new(r"[0-9]+").expect("Invalid regex") }
+    }
+}
+
+impl Default for SumOfNumbers {
+    fn default() -> Self {
+        SumOfNumbers::new()
+    }
+}
+
+impl ExtractionStrategy for SumOfNumbers {
+    fn extract(&self, line: &str) -> Result<usize, CalibrationError> {
+        sum_numbers(self.number.find_iter(line).map(|number| number.as_str()))
+    }
+
+    fn extract_from_matches(&self, matches: &[NumberMatch]) -> Result<usize, CalibrationError> {
+        sum_numbers(digit_runs(matches).iter().map(String::as_str))
+    }
+}
+
+fn sum_numbers<'a>(numbers: impl Iterator<Item = &'a str>) -> Result<usize, CalibrationError> {
+    let mut numbers = numbers.peekable();
+    if numbers.peek().is_none() {
+        return Err(CalibrationError::NoDigits);
+    }
+
+    numbers.try_fold(0usize, |sum, number| {
+        number.parse::<usize>().ok()
+            .and_then(|value| sum.checked_add(value))
+            .ok_or_else(|| CalibrationError::Overflow(number.to_string()))
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExtractionMode {
+    Digits,
+    DigitsAndWords,
+    ConcatenatedDigits,
+    SumOfNumbers,
+}
+
+impl FromStr for ExtractionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "digits" => Ok(ExtractionMode::Digits),
+            "words" => Ok(ExtractionMode::DigitsAndWords),
+            "concat" => Ok(ExtractionMode::ConcatenatedDigits),
+            "sum" => Ok(ExtractionMode::SumOfNumbers),
+            _ => Err(format!("unknown extraction mode '{}'", s)),
+        }
+    }
+}
+
+impl ExtractionMode {
+    pub fn strategy(&self, words: &NumberWords) -> Box<dyn ExtractionStrategy> {
+        match self {
+            ExtractionMode::Digits => Box::new(FirstAndLast::digits()),
+            ExtractionMode::DigitsAndWords => Box::new(FirstAndLast::digits_and_words(words)),
+            ExtractionMode::ConcatenatedDigits => Box::new(ConcatenatedDigits),
+            ExtractionMode::SumOfNumbers => Box::new(SumOfNumbers::new()),
+        }
+    }
+
+    pub fn uses_words(&self) -> bool {
+        *self == ExtractionMode::DigitsAndWords
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digits_only_ignores_words() {
+        let strategy = FirstAndLast::digits();
+        assert_eq!(strategy.extract("two1nine"), Ok(11));
+        assert_eq!(strategy.extract("pqr3stu8vwx"), Ok(38));
+        assert_eq!(strategy.extract("treb7uchet"), Ok(77));
+    }
+
+    #[test]
+    fn test_digits_and_words() {
+        let strategy = FirstAndLast::digits_and_words(&NumberWords::english());
+        assert_eq!(strategy.extract("two1nine"), Ok(29));
+        assert_eq!(strategy.extract("zoneight"), Ok(18));
+    }
+
+    #[test]
+    fn test_concatenated_digits() {
+        assert_eq!(ConcatenatedDigits.extract("a1b2c3d4e5f"), Ok(12345));
+        assert!(matches!(ConcatenatedDigits.extract("1".repeat(30).as_str()), Err(CalibrationError::Overflow(_))));
+    }
+
+    #[test]
+    fn test_sum_of_numbers() {
+        let strategy = SumOfNumbers::new();
+        assert_eq!(strategy.extract("12ab34c5"), Ok(51));
+        assert_eq!(strategy.extract("x007y"), Ok(7));
+    }
+
+    #[test]
+    fn test_sum_of_numbers_ignores_other_digits() {
+        // Arabic-Indic digits are not calibration digits
+        assert_eq!(SumOfNumbers::new().extract("12\u{0663}4"), Ok(16));
+        assert_eq!(SumOfNumbers::new().extract("\u{0663}\u{0664}"), Err(CalibrationError::NoDigits));
+    }
+
+    #[test]
+    fn test_extract_from_matches_agrees_with_extract() {
+        let words = NumberWords::english();
+        let no_words = NumberWords::new(vec![]);
+        for mode in ["digits", "words", "concat", "sum"] {
+            let mode = mode.parse::<ExtractionMode>().unwrap();
+            let scanner = NumberScanner::new(if mode.uses_words() { &words } else { &no_words });
+            let strategy = mode.strategy(&words);
+            for line in ["two1nine", "12ab34c5", "x007y", "abcdef", "eightwo3", &"1".repeat(30)] {
+                assert_eq!(strategy.extract_from_matches(&scanner.matches(line)), strategy.extract(line), "Failed on {:?} line: {}", mode, line);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lines_without_digits_are_errors() {
+        for mode in ["digits", "words", "concat", "sum"] {
+            let strategy = mode.parse::<ExtractionMode>().unwrap().strategy(&NumberWords::english());
+            assert_eq!(strategy.extract("abcdef"), Err(CalibrationError::NoDigits), "Failed on mode: {}", mode);
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use crate::bag_rules::BagRules;
+use crate::island_games::{ln_choose, Game};
+
+// Refuse to enumerate more bag compositions than this
+const MAX_COMPOSITIONS: u64 = 5_000_000;
+
+// Belief about how many cubes of each colour are in the bag before looking at any draws
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Prior {
+    // Every count from the observed minimum up to `max_per_colour` is equally likely
+    Uniform,
+    // Each colour's count is Poisson distributed around `mean`
+    Poisson { mean: f64 },
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct InferenceSettings {
+    pub prior: Prior,
+    // Largest count considered for any colour
+    pub max_per_colour: u32,
+    // Width of the credible interval, e.g. 0.9 for a 90% interval
+    pub confidence: f64,
+}
+
+impl Default for InferenceSettings {
+    fn default() -> Self {
+        InferenceSettings { prior: Prior::Uniform, max_per_colour: 30, confidence: 0.9 }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ColourEstimate {
+    pub colour: String,
+    pub most_likely: u32,
+    pub mean: f64,
+    // Central credible interval, inclusive at both ends
+    pub interval: (u32, u32),
+}
+
+// How well a claimed bag fits the draws
+#[derive(Debug, PartialEq, Clone)]
+pub struct ClaimAssessment {
+    // Posterior probability of exactly the claimed bag
+    pub posterior: f64,
+    // Posterior of the claimed bag divided by the posterior of the most likely bag
+    pub relative_to_most_likely: f64,
+    // Colours whose claimed count falls outside the credible interval
+    pub outside_interval: Vec<String>,
+}
+
+// Posterior over bag compositions given a game's draws. Each draw is taken without
+// replacement and the cubes go back in the bag before the next draw.
+#[derive(Debug, Clone)]
+pub struct BagEstimate {
+    pub colours: Vec<ColourEstimate>,
+    lower: Vec<u32>,
+    sizes: Vec<u32>,
+    posterior: Vec<f64>,
+    most_likely: usize,
+}
+
+impl BagEstimate {
+    pub fn most_likely_bag(&self) -> BagRules {
+        BagRules::new(self.colours.iter()
+            .map(|estimate| (estimate.colour.clone(), estimate.most_likely))
+            .collect())
+    }
+
+    pub fn assess(&self, claimed: &BagRules) -> ClaimAssessment {
+        let counts: Vec<u32> = self.colours.iter().map(|estimate| claimed.limit(&estimate.colour)).collect();
+        let posterior = self.index_of(&counts).map_or(0.0, |index| self.posterior[index]);
+
+        ClaimAssessment {
+            posterior,
+            relative_to_most_likely: posterior / self.posterior[self.most_likely],
+            outside_interval: self.colours.iter()
+                .zip(&counts)
+                .filter(|(estimate, count)| **count < estimate.interval.0 || **count > estimate.interval.1)
+                .map(|(estimate, _)| estimate.colour.clone())
+                .collect(),
+        }
+    }
+
+    fn index_of(&self, counts: &[u32]) -> Option<usize> {
+        let mut index = 0;
+        for ((count, lower), size) in counts.iter().zip(&self.lower).zip(&self.sizes) {
+            if *count < *lower || *count - lower >= *size {
+                return None;
+            }
+            index = index * *size as usize + (count - lower) as usize;
+        }
+        Some(index)
+    }
+}
+
+// Decodes a composition index into the count for each colour
+fn counts_at(mut index: usize, lower: &[u32], sizes: &[u32]) -> Vec<u32> {
+    let mut counts = vec![0; sizes.len()];
+    for position in (0..sizes.len()).rev() {
+        counts[position] = lower[position] + (index % sizes[position] as usize) as u32;
+        index /= sizes[position] as usize;
+    }
+    counts
+}
+
+fn ln_prior(prior: Prior, count: u32) -> f64 {
+    match prior {
+        Prior::Uniform => 0.0,
+        Prior::Poisson { mean } => {
+            let ln_factorial: f64 = (1..=count).map(|i| (i as f64).ln()).sum();
+            count as f64 * mean.ln() - mean - ln_factorial
+        }
+    }
+}
+
+// Written so that NaN fails each check too
+fn check_settings(settings: &InferenceSettings) -> Result<(), String> {
+    if !(settings.confidence > 0.0 && settings.confidence <= 1.0) {
+        return Err(format!("confidence must be above 0 and at most 1, got {}", settings.confidence));
+    }
+    if let Prior::Poisson { mean } = settings.prior {
+        if !(mean > 0.0 && mean.is_finite()) {
+            return Err(format!("poisson prior mean must be above 0, got {}", mean));
+        }
+    }
+    Ok(())
+}
+
+// Estimates the bag for `game` over `colours`; colours never drawn may still be listed
+pub fn infer_bag(game: &Game, colours: &[String], settings: &InferenceSettings) -> Result<BagEstimate, String> {
+    check_settings(settings)?;
+    if colours.is_empty() {
+        return Err("no colours to estimate".to_string());
+    }
+    let position: HashMap<&str, usize> = colours.iter().enumerate().map(|(i, colour)| (colour.as_str(), i)).collect();
+
+    // Per-draw counts in `colours` order
+    let mut draws: Vec<Vec<u32>> = Vec::new();
+    for draw in &game.draws {
+        let mut counts = vec![0; colours.len()];
+        for reveal in &draw.reveals {
+            let index = *position.get(reveal.colour())
+                .ok_or_else(|| format!("colour {} was drawn but is not being estimated", reveal.colour()))?;
+            counts[index] += reveal.count();
+        }
+        draws.push(counts);
+    }
+
+    let lower: Vec<u32> = (0..colours.len())
+        .map(|i| draws.iter().map(|counts| counts[i]).max().unwrap_or(0))
+        .collect();
+    let sizes: Vec<u32> = lower.iter().map(|low| settings.max_per_colour.max(*low) - low + 1).collect();
+    let total = sizes.iter().try_fold(1u64, |total, size| total.checked_mul(*size as u64))
+        .filter(|total| *total <= MAX_COMPOSITIONS)
+        .ok_or_else(|| format!("more than {} bag compositions to consider", MAX_COMPOSITIONS))? as usize;
+
+    let mut ln_posterior = Vec::with_capacity(total);
+    for index in 0..total {
+        let counts = counts_at(index, &lower, &sizes);
+        let bag_total: u32 = counts.iter().sum();
+
+        let mut ln_p: f64 = counts.iter().map(|count| ln_prior(settings.prior, *count)).sum();
+        for drawn in &draws {
+            let drawn_total: u32 = drawn.iter().sum();
+            ln_p += counts.iter().zip(drawn).map(|(count, k)| ln_choose(*count, *k)).sum::<f64>()
+                - ln_choose(bag_total, drawn_total);
+        }
+        ln_posterior.push(ln_p);
+    }
+
+    // Normalise with log-sum-exp to keep small likelihoods from underflowing
+    let most_likely = (0..total)
+        .fold(0, |best, index| if ln_posterior[index] > ln_posterior[best] { index } else { best });
+    let max_ln = ln_posterior[most_likely];
+    let ln_norm = max_ln + ln_posterior.iter().map(|ln_p| (ln_p - max_ln).exp()).sum::<f64>().ln();
+    let posterior: Vec<f64> = ln_posterior.iter().map(|ln_p| (ln_p - ln_norm).exp()).collect();
+
+    let mut marginals: Vec<Vec<f64>> = sizes.iter().map(|size| vec![0.0; *size as usize]).collect();
+    for (index, p) in posterior.iter().enumerate() {
+        for (colour, count) in counts_at(index, &lower, &sizes).iter().enumerate() {
+            marginals[colour][(count - lower[colour]) as usize] += p;
+        }
+    }
+
+    let best_counts = counts_at(most_likely, &lower, &sizes);
+    let tail = (1.0 - settings.confidence) / 2.0;
+    let estimates = colours.iter().enumerate().map(|(i, colour)| {
+        let marginal = &marginals[i];
+        let mean = marginal.iter().enumerate().map(|(offset, p)| (lower[i] + offset as u32) as f64 * p).sum();
+        ColourEstimate {
+            colour: colour.clone(),
+            most_likely: best_counts[i],
+            mean,
+            interval: (lower[i] + quantile(marginal, tail), lower[i] + quantile(marginal, 1.0 - tail)),
+        }
+    }).collect();
+
+    Ok(BagEstimate { colours: estimates, lower, sizes, posterior, most_likely })
+}
+
+// Smallest offset whose cumulative probability reaches `q`
+fn quantile(marginal: &[f64], q: f64) -> u32 {
+    let mut cumulative = 0.0;
+    for (offset, p) in marginal.iter().enumerate() {
+        cumulative += p;
+        if cumulative >= q - 1e-12 {
+            return offset as u32;
+        }
+    }
+    marginal.len() as u32 - 1
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::island_games::parse_draws;
+
+    fn colours(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn single_colour_with_uniform_prior_is_flat_above_the_minimum() {
+        let game = Game::new(1, parse_draws("3 red; 2 red").unwrap());
+        let settings = InferenceSettings { max_per_colour: 5, ..InferenceSettings::default() };
+        let estimate = infer_bag(&game, &colours(&["red"]), &settings).unwrap();
+
+        assert_eq!(estimate.colours[0].most_likely, 3);
+        assert!((estimate.colours[0].mean - 4.0).abs() < 1e-9);
+        assert_eq!(estimate.colours[0].interval, (3, 5));
+    }
+
+    #[test]
+    fn poisson_prior_pulls_towards_its_mean() {
+        let game = Game::new(1, parse_draws("3 red").unwrap());
+        let settings = InferenceSettings { prior: Prior::Poisson { mean: 5.5 }, ..InferenceSettings::default() };
+        let estimate = infer_bag(&game, &colours(&["red"]), &settings).unwrap();
+
+        assert_eq!(estimate.colours[0].most_likely, 5);
+    }
+
+    #[test]
+    fn balanced_draws_point_to_a_balanced_bag() {
+        // Pairs come out mixed 3 times in 5, which a bag of 3 red and 3 blue gives exactly,
+        // so the most likely bag sits above both the observed minimum of 2 and the flat prior
+        let draws = [vec!["2 red"; 20], vec!["2 blue"; 20], vec!["1 red, 1 blue"; 60]].concat().join("; ");
+        let game = Game::new(1, parse_draws(&draws).unwrap());
+        let settings = InferenceSettings { max_per_colour: 20, ..InferenceSettings::default() };
+        let estimate = infer_bag(&game, &colours(&["red", "blue"]), &settings).unwrap();
+
+        assert_eq!(estimate.colours[0].most_likely, 3);
+        assert_eq!(estimate.colours[1].most_likely, 3);
+    }
+
+    #[test]
+    fn claimed_bag_below_the_observed_minimum_is_impossible() {
+        let game = Game::new(1, parse_draws("3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green").unwrap());
+        let estimate = infer_bag(&game, &colours(&["blue", "green", "red"]), &InferenceSettings::default()).unwrap();
+
+        let claimed = BagRules::new(HashMap::from([
+            ("red".to_string(), 2),
+            ("green".to_string(), 13),
+            ("blue".to_string(), 14),
+        ]));
+        let assessment = estimate.assess(&claimed);
+        assert_eq!(assessment.posterior, 0.0);
+        assert_eq!(assessment.outside_interval, vec!["red".to_string()]);
+
+        let best = estimate.assess(&estimate.most_likely_bag());
+        assert!((best.relative_to_most_likely - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn settings_are_checked() {
+        let game = Game::new(1, parse_draws("3 red").unwrap());
+        let red = colours(&["red"]);
+        for confidence in [0.0, -0.5, 1.5, f64::NAN] {
+            let settings = InferenceSettings { confidence, ..InferenceSettings::default() };
+            assert!(infer_bag(&game, &red, &settings).is_err(), "confidence {}", confidence);
+        }
+        for mean in [0.0, -2.0, f64::NAN, f64::INFINITY] {
+            let settings = InferenceSettings { prior: Prior::Poisson { mean }, ..InferenceSettings::default() };
+            assert!(infer_bag(&game, &red, &settings).is_err(), "mean {}", mean);
+        }
+        let settings = InferenceSettings { confidence: 1.0, ..InferenceSettings::default() };
+        assert_eq!(infer_bag(&game, &red, &settings).unwrap().colours[0].interval, (3, 30));
+    }
+
+    #[test]
+    fn drawn_colours_must_be_estimated() {
+        let game = Game::new(1, parse_draws("3 blue, 4 red").unwrap());
+        assert!(infer_bag(&game, &colours(&["red"]), &InferenceSettings::default()).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use serde::Deserialize;
+
+// Describes what is in the bag: a limit per colour and, optionally, a limit on the total number of cubes.
+// Loaded from TOML:
+//
+//     max_total = 39
+//     [colours]
+//     red = 12
+//     green = 13
+//     blue = 14
+//
+// or the equivalent JSON object.
+#[derive(Debug, PartialEq, Default, Clone, Deserialize)]
+pub struct BagRules {
+    pub colours: HashMap<String, u32>,
+    #[serde(default)]
+    pub max_total: Option<u32>,
+}
+
+#[derive(Debug)]
+pub enum BagRulesError {
+    Io(String),
+    Parse(String),
+    UnsupportedFormat(String),
+}
+
+impl fmt::Display for BagRulesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BagRulesError::Io(message) => write!(f, "could not read bag file: {}", message),
+            BagRulesError::Parse(message) => write!(f, "invalid bag file: {}", message),
+            BagRulesError::UnsupportedFormat(path) => {
+                write!(f, "bag file {} must be .toml or .json", path)
+            }
+        }
+    }
+}
+
+// Why a game could not have been played with the bag
+#[derive(Debug, PartialEq, Clone)]
+pub enum RuleViolation {
+    // A single draw showed more cubes of a colour than the bag holds (`limit` is 0 for unknown colours)
+    ColourLimit { draw: usize, colour: String, count: u32, limit: u32 },
+    // The game needs more cubes in total than the bag can hold
+    TotalLimit { required: u32, limit: u32 },
+}
+
+impl RuleViolation {
+    pub fn excess(&self) -> u32 {
+        match self {
+            RuleViolation::ColourLimit { count, limit, .. } => count - limit,
+            RuleViolation::TotalLimit { required, limit } => required - limit,
+        }
+    }
+}
+
+impl fmt::Display for RuleViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleViolation::ColourLimit { draw, colour, count, limit } => write!(
+                f,
+                "draw {} shows {} {}, {} over the limit of {}",
+                draw, count, colour, self.excess(), limit
+            ),
+            RuleViolation::TotalLimit { required, limit } => write!(
+                f,
+                "needs at least {} cubes, {} over the bag total of {}",
+                required, self.excess(), limit
+            ),
+        }
+    }
+}
+
+impl BagRules {
+    pub fn new(colours: HashMap<String, u32>) -> Self {
+        BagRules { colours, max_total: None }
+    }
+
+    pub fn with_max_total(mut self, max_total: u32) -> Self {
+        self.max_total = Some(max_total);
+        self
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, BagRulesError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|e| BagRulesError::Io(e.to_string()))?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => BagRules::from_toml(&contents),
+            Some("json") => BagRules::from_json(&contents),
+            _ => Err(BagRulesError::UnsupportedFormat(path.display().to_string())),
+        }
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, BagRulesError> {
+        toml::from_str(contents).map_err(|e| BagRulesError::Parse(e.to_string()))
+    }
+
+    pub fn from_json(contents: &str) -> Result<Self, BagRulesError> {
+        serde_json::from_str(contents).map_err(|e| BagRulesError::Parse(e.to_string()))
+    }
+
+    pub fn limit(&self, colour: &str) -> u32 {
+        self.colours.get(colour).copied().unwrap_or(0)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_bag_from_toml() {
+        let bag = BagRules::from_toml("max_total = 40\n[colours]\nred = 12\nyellow = 3\n").unwrap();
+        assert_eq!(bag.limit("red"), 12);
+        assert_eq!(bag.limit("yellow"), 3);
+        assert_eq!(bag.limit("blue"), 0);
+        assert_eq!(bag.max_total, Some(40));
+    }
+
+    #[test]
+    fn test_load_bag_from_json() {
+        let bag = BagRules::from_json(r#"{ "colours": { "red": 12, "green": 13, "blue": 14 } }"#).unwrap();
+        let expected = BagRules::new(HashMap::from([
+            ("red".to_string(), 12),
+            ("green".to_string(), 13),
+            ("blue".to_string(), 14),
+        ]));
+        assert_eq!(bag, expected);
+    }
+
+    #[test]
+    fn test_violation_reports_excess() {
+        let violation = RuleViolation::ColourLimit { draw: 3, colour: "red".to_string(), count: 20, limit: 12 };
+        assert_eq!(violation.excess(), 8);
+        assert_eq!(violation.to_string(), "draw 3 shows 20 red, 8 over the limit of 12");
+    }
+}
@@ -0,0 +1,302 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::island_games::{Draw, Game, GameReveal};
+
+// A problem in a game file, pointing at the line and the columns where parsing stopped
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    // 1-based column of the first offending character
+    pub column: usize,
+    // number of characters the caret underline covers
+    pub width: usize,
+    pub expected: String,
+    pub found: String,
+    pub source_line: String,
+}
+
+impl ParseError {
+    // Renders the error with the source line and a caret under the offending text:
+    //
+    //     error: line 3, column 8: expected ':', found "8"
+    //       |
+    //     3 | Game 3 8 green
+    //       |        ^
+    pub fn render(&self) -> String {
+        let gutter = " ".repeat(self.line.to_string().len());
+        format!(
+            "error: {}\n{} |\n{} | {}\n{} | {}{}",
+            self,
+            gutter,
+            self.line, self.source_line,
+            gutter, " ".repeat(self.column - 1), "^".repeat(self.width)
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: expected {}, found {}", self.line, self.column, self.expected, self.found)
+    }
+}
+
+struct LineParser<'a> {
+    line: &'a str,
+    line_number: usize,
+    position: usize,
+}
+
+impl<'a> LineParser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.line[self.position..]
+    }
+
+    fn skip_spaces(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.position = self.line.len() - trimmed.len();
+    }
+
+    // Error at the current position, underlining the next token (or the end of the line)
+    fn error(&self, expected: &str) -> ParseError {
+        let token: String = self.rest().chars().take_while(|ch| !ch.is_whitespace()).collect();
+        let token = if token.is_empty() { self.rest().chars().take(1).collect() } else { token };
+
+        ParseError {
+            line: self.line_number,
+            column: self.line[..self.position].chars().count() + 1,
+            width: token.chars().count().max(1),
+            expected: expected.to_string(),
+            found: if token.is_empty() { "end of line".to_string() } else { format!("\"{}\"", token) },
+            source_line: self.line.to_string(),
+        }
+    }
+
+    fn expect_literal(&mut self, literal: &str, expected: &str) -> Result<(), ParseError> {
+        if self.rest().starts_with(literal) {
+            self.position += literal.len();
+            Ok(())
+        } else {
+            Err(self.error(expected))
+        }
+    }
+
+    fn expect_number(&mut self, expected: &str) -> Result<u32, ParseError> {
+        let digits = self.rest().len() - self.rest().trim_start_matches(|ch: char| ch.is_ascii_digit()).len();
+        let number = self.rest()[..digits].parse::<u32>().map_err(|_| self.error(expected))?;
+        self.position += digits;
+        Ok(number)
+    }
+
+    fn expect_word(&mut self, expected: &str) -> Result<&'a str, ParseError> {
+        let letters = self.rest().len() - self.rest().trim_start_matches(|ch: char| ch.is_alphanumeric() || ch == '_').len();
+        if letters == 0 {
+            return Err(self.error(expected));
+        }
+        let word = &self.rest()[..letters];
+        self.position += letters;
+        Ok(word)
+    }
+
+    fn expect_end(&mut self, expected: &str) -> Result<(), ParseError> {
+        self.skip_spaces();
+        if self.rest().is_empty() {
+            Ok(())
+        } else {
+            Err(self.error(expected))
+        }
+    }
+
+    // "3 blue"
+    fn parse_reveal(&mut self) -> Result<GameReveal, ParseError> {
+        self.skip_spaces();
+        let count = self.expect_number("cube count")?;
+        if !self.rest().starts_with(char::is_whitespace) {
+            return Err(self.error("space after the cube count"));
+        }
+        self.skip_spaces();
+        let colour = self.expect_word("cube colour")?;
+        Ok(GameReveal::new(colour, count))
+    }
+
+    // ","-separated reveals, stopping at the first thing that isn't a ','
+    fn parse_draw(&mut self) -> Result<Draw, ParseError> {
+        let mut reveals = vec![self.parse_reveal()?];
+        loop {
+            self.skip_spaces();
+            if !self.rest().starts_with(',') {
+                return Ok(Draw { reveals });
+            }
+            self.position += 1;
+            reveals.push(self.parse_reveal()?);
+        }
+    }
+
+    // ";"-separated draws running to the end of the line
+    fn parse_draws(&mut self) -> Result<Vec<Draw>, ParseError> {
+        let mut draws = vec![self.parse_draw()?];
+        loop {
+            match self.rest().chars().next() {
+                Some(';') => {
+                    self.position += 1;
+                    draws.push(self.parse_draw()?);
+                }
+                None => return Ok(draws),
+                Some(_) => return Err(self.error("',' or ';' or end of line")),
+            }
+        }
+    }
+
+    fn parse_game(&mut self) -> Result<Game, ParseError> {
+        self.skip_spaces();
+        self.expect_literal("Game", "\"Game\"")?;
+        self.skip_spaces();
+        let id = self.expect_number("game id")?;
+        self.skip_spaces();
+        self.expect_literal(":", "':' after the game id")?;
+        Ok(Game::new(id, self.parse_draws()?))
+    }
+}
+
+impl FromStr for GameReveal {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = LineParser { line: s, line_number: 1, position: 0 };
+        let reveal = parser.parse_reveal()?;
+        parser.expect_end("end of line")?;
+        Ok(reveal)
+    }
+}
+
+impl FromStr for Draw {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = LineParser { line: s, line_number: 1, position: 0 };
+        let draw = parser.parse_draw()?;
+        parser.expect_end("',' or end of line")?;
+        Ok(draw)
+    }
+}
+
+// Parses the draws of a game, the part of a line after "Game N:"
+pub fn parse_draws(input: &str) -> Result<Vec<Draw>, ParseError> {
+    LineParser { line: input, line_number: 1, position: 0 }.parse_draws()
+}
+
+impl FromStr for Game {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_game_line(s, 1)
+    }
+}
+
+// Parses one "Game N: ..." line; `line_number` is only used for error reporting
+pub fn parse_game_line(line: &str, line_number: usize) -> Result<Game, ParseError> {
+    LineParser { line, line_number, position: 0 }.parse_game()
+}
+
+// Parses a whole game file, stopping at the first malformed line. Blank lines are ignored.
+pub fn parse_games(contents: &str) -> Result<Vec<Game>, ParseError> {
+    contents.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| parse_game_line(line, index + 1))
+        .collect()
+}
+
+// Parses every line it can and returns the errors for the ones it could not
+pub fn parse_games_lenient(contents: &str) -> (Vec<Game>, Vec<ParseError>) {
+    let mut games = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_game_line(line, index + 1) {
+            Ok(game) => games.push(game),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    (games, errors)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_game_line() {
+        let game = parse_game_line("Game 12: 3 blue, 4 red; 1 red, 2 green", 1).unwrap();
+        assert_eq!(game, Game::new(12, parse_draws("3 blue, 4 red; 1 red, 2 green").unwrap()));
+    }
+
+    #[test]
+    fn game_from_str() {
+        let game = "Game 5: 6 red, 1 blue".parse::<Game>().unwrap();
+        assert_eq!(game.id, 5);
+        assert_eq!(game.max_cubes_drawn(), 7);
+    }
+
+    #[test]
+    fn reports_bad_game_id() {
+        let error = parse_game_line("Game x: 3 blue", 4).unwrap_err();
+        assert_eq!((error.line, error.column, error.width), (4, 6, 2));
+        assert_eq!(error.expected, "game id");
+        assert_eq!(error.found, "\"x:\"");
+    }
+
+    #[test]
+    fn reports_bad_reveal_with_caret() {
+        let error = parse_game_line("Game 3: 8 green, six blue", 3).unwrap_err();
+        assert_eq!(error.expected, "cube count");
+        assert_eq!(error.render(), "\
+error: line 3, column 18: expected cube count, found \"six\"
+  |
+3 | Game 3: 8 green, six blue
+  |                  ^^^");
+    }
+
+    #[test]
+    fn reports_missing_colour_at_end_of_line() {
+        let error = parse_game_line("Game 1: 3 blue, 4", 1).unwrap_err();
+        assert_eq!(error.expected, "space after the cube count");
+        assert_eq!(error.found, "end of line");
+        assert_eq!(error.column, 18);
+    }
+
+    #[test]
+    fn draws_and_reveals_report_positions() {
+        assert_eq!("3 blue".parse::<GameReveal>(), Ok(GameReveal::new("blue", 3)));
+        assert_eq!("3 blue, 4 red".parse::<Draw>().unwrap().reveals.len(), 2);
+        assert_eq!(parse_draws("3 blue; 4 red").unwrap().len(), 2);
+
+        let error = "3 blue, 4 red; 1 green".parse::<Draw>().unwrap_err();
+        assert_eq!((error.column, error.expected.as_str()), (14, "',' or end of line"));
+        let error = "3 blue red".parse::<GameReveal>().unwrap_err();
+        assert_eq!((error.column, error.found.as_str()), (8, "\"red\""));
+        let error = parse_draws("3 blue, four red").unwrap_err();
+        assert_eq!((error.column, error.expected.as_str()), (9, "cube count"));
+    }
+
+    #[test]
+    fn strict_parsing_stops_at_first_error() {
+        let contents = "Game 1: 3 blue\n\nGame 2 4 red\nGame 3: 1 green, 2\n";
+        let error = parse_games(contents).unwrap_err();
+        assert_eq!(error.line, 3);
+    }
+
+    #[test]
+    fn lenient_parsing_collects_every_error() {
+        let contents = "Game 1: 3 blue\nGame 2 4 red\nGame 3: 1 green; 2 pink\nGame 4: 1 green 2 red\n";
+        let (games, errors) = parse_games_lenient(contents);
+
+        assert_eq!(games.iter().map(|game| game.id).collect::<Vec<u32>>(), vec![1, 3]);
+        assert_eq!(errors.iter().map(|error| error.line).collect::<Vec<usize>>(), vec![2, 4]);
+        assert_eq!(errors[1].expected, "',' or ';' or end of line");
+    }
+}
@@ -2,23 +2,11 @@
 
 
 
-use std::collections::HashMap;
-use std::str::FromStr;
-use regex::Regex;
-use lazy_static::lazy_static;
-
-// Define a custom error type for parsing
-#[derive(Debug)]
-pub enum GameRevealError {
-    RegexError(String),
-    ParseError(String),
-}
-
-// Lazy static for compiling the regex only once
-lazy_static! {
-    static ref RE: Regex = Regex::new(r"^(\d+) (\w+)$").expect("Invalid regex");
-}
+use std::collections::{HashMap, HashSet};
+use crate::bag_rules::{BagRules, RuleViolation};
 
+// Reveals, draws and games are read by `game_parser`, which reports where a line went wrong
+pub use crate::game_parser::parse_draws;
 
 #[derive(Debug, PartialEq, Default, Clone)]
 pub struct GameReveal {
@@ -26,58 +14,164 @@ pub struct GameReveal {
     cube_count: u32
 }
 
-impl FromStr for GameReveal {
-    type Err = GameRevealError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let captures = RE.captures(s).ok_or_else(|| GameRevealError::RegexError(s.to_string()))?;
+impl GameReveal {
+    pub fn new(cube_colour: &str, cube_count: u32) -> Self {
+        GameReveal { cube_colour: cube_colour.to_string(), cube_count }
+    }
 
-        let cube_count = captures.get(1)
-            .and_then(|m| m.as_str().parse::<u32>().ok())
-            .ok_or_else(|| GameRevealError::ParseError(s.to_string()))?;
+    pub fn colour(&self) -> &str {
+        &self.cube_colour
+    }
 
-        let cube_colour = captures.get(2)
-            .map(|m| m.as_str().to_string())
-            .ok_or_else(|| GameRevealError::ParseError(s.to_string()))?;
+    pub fn count(&self) -> u32 {
+        self.cube_count
+    }
 
-        Ok(GameReveal { cube_colour, cube_count })
+    // Checks this reveal against the bag; `draw` is the 1-based number of the draw it came from
+    pub fn check(&self, draw: usize, bag: &BagRules) -> Option<RuleViolation> {
+        let limit = bag.limit(&self.cube_colour);
+        if self.cube_count > limit {
+            Some(RuleViolation::ColourLimit {
+                draw,
+                colour: self.cube_colour.clone(),
+                count: self.cube_count,
+                limit,
+            })
+        } else {
+            None
+        }
     }
 }
 
-pub fn parse_game_reveals(input: String) -> Result<Vec<GameReveal>, GameRevealError> {
-    let mut game_reveals = Vec::new();
+// One handful of cubes shown from the bag, the `;`-separated parts of a game line
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct Draw {
+    pub reveals: Vec<GameReveal>,
+}
+
+impl Draw {
+    pub fn total_cubes(&self) -> u32 {
+        self.reveals.iter().map(|reveal| reveal.cube_count).sum()
+    }
 
-    for part in input.split(';') {
-        for game_reveal_str in part.trim().split(',') {
-            let game_reveal = game_reveal_str.trim().parse::<GameReveal>()?;
-            game_reveals.push(game_reveal);
+    // Cubes of each colour in the handful, adding up colours that are shown more than once,
+    // in the order each colour first appears
+    pub fn cube_counts(&self) -> Vec<GameReveal> {
+        let mut counts: Vec<GameReveal> = Vec::new();
+        for reveal in &self.reveals {
+            match counts.iter_mut().find(|count| count.cube_colour == reveal.cube_colour) {
+                Some(count) => count.cube_count += reveal.cube_count,
+                None => counts.push(reveal.clone()),
+            }
         }
+        counts
+    }
+
+    pub fn repeats_colour(&self) -> bool {
+        let mut seen = HashSet::new();
+        !self.reveals.iter().all(|reveal| seen.insert(reveal.cube_colour.as_str()))
+    }
+
+    // Chance of pulling exactly this handful out of the bag, drawing without replacement
+    pub fn probability(&self, bag: &BagRules) -> f64 {
+        let mut drawn: HashMap<&str, u32> = HashMap::new();
+        for reveal in &self.reveals {
+            *drawn.entry(reveal.cube_colour.as_str()).or_insert(0) += reveal.cube_count;
+        }
+
+        let bag_total: u32 = bag.colours.values().sum();
+        let drawn_total: u32 = drawn.values().sum();
+        if drawn.iter().any(|(colour, count)| *count > bag.limit(colour)) || drawn_total > bag_total {
+            return 0.0;
+        }
+
+        let ln_ways: f64 = drawn.iter()
+            .map(|(colour, count)| ln_choose(bag.limit(colour), *count))
+            .sum();
+        (ln_ways - ln_choose(bag_total, drawn_total)).exp()
+    }
+}
+
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct Game {
+    pub id: u32,
+    pub draws: Vec<Draw>,
+}
+
+impl Game {
+    pub fn new(id: u32, draws: Vec<Draw>) -> Self {
+        Game { id, draws }
+    }
+
+    // Every reveal in the game, across all draws
+    pub fn reveals(&self) -> impl Iterator<Item = &GameReveal> {
+        self.draws.iter().flat_map(|draw| draw.reveals.iter())
+    }
+
+    pub fn max_cubes_drawn(&self) -> u32 {
+        self.draws.iter().map(Draw::total_cubes).max().unwrap_or(0)
+    }
+
+    pub fn any_draw_repeats_colour(&self) -> bool {
+        self.draws.iter().any(Draw::repeats_colour)
     }
 
-    Ok(game_reveals)
+    pub fn draw_probabilities(&self, bag: &BagRules) -> Vec<f64> {
+        self.draws.iter().map(|draw| draw.probability(bag)).collect()
+    }
+
+    // The fewest cubes of each colour the bag must have held for this game
+    pub fn minimum_cubes(&self) -> Vec<GameReveal> {
+        calculate_minimum_possible_stones(self.draws.iter().flat_map(Draw::cube_counts).collect())
+    }
+}
+
+// Natural log of n choose k
+pub(crate) fn ln_choose(n: u32, k: u32) -> f64 {
+    (1..=k).map(|i| ((n - k + i) as f64 / i as f64).ln()).sum()
 }
 
 
-pub fn calculate_possible_games(reveals: HashMap<u32, Vec<GameReveal>>, available_cubes: HashMap<String, u32>) -> Vec<u32> {
-    reveals.into_iter()
-        .filter(|(_, game_reveals)| is_game_possible(game_reveals.to_vec(), available_cubes.clone()))
-        .map(|(game_id, _)| game_id)
+pub fn calculate_possible_games(games: &[Game], bag: &BagRules) -> Vec<u32> {
+    games.iter()
+        .filter(|game| is_game_possible(game, bag))
+        .map(|game| game.id)
         .collect()
 }
 
-fn is_game_possible(mut reveals: Vec<GameReveal>, mut available_cubes: HashMap<String, u32>) -> bool {
-    if reveals.is_empty() {
-        return true;
+pub fn is_game_possible(game: &Game, bag: &BagRules) -> bool {
+    check_game(game, bag).is_empty()
+}
+
+// Every rule the game breaks, in draw order, followed by the total-cube rule if it is broken.
+// A colour shown twice in one draw counts as all of those cubes at once.
+pub fn check_game(game: &Game, bag: &BagRules) -> Vec<RuleViolation> {
+    let mut violations: Vec<RuleViolation> = game.draws.iter()
+        .enumerate()
+        .flat_map(|(index, draw)| draw.cube_counts().into_iter().filter_map(move |count| count.check(index + 1, bag)))
+        .collect();
+
+    if let Some(limit) = bag.max_total {
+        let required = game.minimum_cubes()
+            .iter()
+            .map(|cube| cube.cube_count)
+            .sum::<u32>();
+        if required > limit {
+            violations.push(RuleViolation::TotalLimit { required, limit });
+        }
     }
 
-    let reveal = reveals.pop().unwrap();
+    violations
+}
 
-    match available_cubes.get_mut(&reveal.cube_colour) {
-        Some(available) if *available >= reveal.cube_count => {
-            is_game_possible(reveals, available_cubes)
-        },
-        _ => false,
-    }
+// The impossible games and why each one failed, ordered by game id
+pub fn explain_impossible_games(games: &[Game], bag: &BagRules) -> Vec<(u32, Vec<RuleViolation>)> {
+    let mut impossible: Vec<(u32, Vec<RuleViolation>)> = games.iter()
+        .map(|game| (game.id, check_game(game, bag)))
+        .filter(|(_, violations)| !violations.is_empty())
+        .collect();
+    impossible.sort_by_key(|(game_id, _)| *game_id);
+    impossible
 }
 
 fn calculate_minimum_possible_stones(input: Vec<GameReveal>) -> Vec<GameReveal> {
@@ -103,10 +197,10 @@ fn calculate_power_of_cubes(min_cubes: Vec<GameReveal>) -> u32 {
     power
 }
 
-pub fn caclulate_powers_of_min_games(input: HashMap<u32, Vec<GameReveal>>) -> Vec<u32> {
+pub fn caclulate_powers_of_min_games(games: &[Game]) -> Vec<u32> {
     let mut powers = Vec::new();
-    for (_, reveals) in input {
-        let min_cubes = calculate_minimum_possible_stones(reveals);
+    for game in games {
+        let min_cubes = game.minimum_cubes();
         let power = calculate_power_of_cubes(min_cubes);
         powers.push(power);
     }
@@ -124,7 +218,7 @@ mod tests {
 
     #[test]
     fn test_game_reveal_from_str() {
-        let reveal = GameReveal::from_str("3 blue").unwrap();
+        let reveal = "3 blue".parse::<GameReveal>().unwrap();
         assert_eq!(reveal, GameReveal {
             cube_colour: "blue".to_string(),
             cube_count: 3,
@@ -148,12 +242,12 @@ mod tests {
                 cube_count: 1,
             },
         ];
-        let available_cubes = HashMap::from([
+        let available_cubes = BagRules::new(HashMap::from([
             ("red".to_string(), 1),
             ("green".to_string(), 3),
             ("blue".to_string(), 1),
-        ]);
-        assert_eq!(is_game_possible(reveals, available_cubes), true);
+        ]));
+        assert!(is_game_possible(&Game::new(1, vec![Draw { reveals }]), &available_cubes));
     }
 
 
@@ -177,12 +271,12 @@ mod tests {
                 cube_count: 1,
             },
         ];
-        let available_cubes = HashMap::from([
+        let available_cubes = BagRules::new(HashMap::from([
             ("red".to_string(), 1),
             ("green".to_string(), 1),
             ("blue".to_string(), 1),
-        ]);
-        assert_eq!(is_game_possible(reveals, available_cubes), false);
+        ]));
+        assert!(!is_game_possible(&Game::new(1, vec![Draw { reveals }]), &available_cubes));
     }
 
 
@@ -190,41 +284,134 @@ mod tests {
     // a test for calculate_possible_games
     #[test]
     fn test_calculate_possible_games() {
-        let mut reveals = HashMap::new();
-        reveals.insert(1, vec![
-            GameReveal {
-                cube_colour: "red".to_string(),
-                cube_count: 1,
-            },
-            GameReveal {
-                cube_colour: "green".to_string(),
-                cube_count: 1,
-            },
-            GameReveal {
-                cube_colour: "blue".to_string(),
-                cube_count: 1,
-            },
-        ]);
-        reveals.insert(2, vec![
-            GameReveal {
-                cube_colour: "red".to_string(),
-                cube_count: 1,
-            },
-            GameReveal {
-                cube_colour: "green".to_string(),
-                cube_count: 5,
-            },
-            GameReveal {
-                cube_colour: "blue".to_string(),
-                cube_count: 1,
-            },
-        ]);
-        let available_cubes = HashMap::from([
+        let games = vec![
+            Game::new(1, vec![Draw { reveals: vec![
+                GameReveal {
+                    cube_colour: "red".to_string(),
+                    cube_count: 1,
+                },
+                GameReveal {
+                    cube_colour: "green".to_string(),
+                    cube_count: 1,
+                },
+                GameReveal {
+                    cube_colour: "blue".to_string(),
+                    cube_count: 1,
+                },
+            ] }]),
+            Game::new(2, vec![Draw { reveals: vec![
+                GameReveal {
+                    cube_colour: "red".to_string(),
+                    cube_count: 1,
+                },
+                GameReveal {
+                    cube_colour: "green".to_string(),
+                    cube_count: 5,
+                },
+                GameReveal {
+                    cube_colour: "blue".to_string(),
+                    cube_count: 1,
+                },
+            ] }]),
+        ];
+        let available_cubes = BagRules::new(HashMap::from([
             ("red".to_string(), 1),
             ("green".to_string(), 3),
             ("blue".to_string(), 1),
+        ]));
+        assert_eq!(calculate_possible_games(&games, &available_cubes), vec![1]);
+    }
+
+    #[test]
+    fn explains_which_draw_broke_the_bag() {
+        let game = Game::new(3, parse_draws("8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green").unwrap());
+        let bag = BagRules::new(HashMap::from([
+            ("red".to_string(), 12),
+            ("green".to_string(), 13),
+            ("blue".to_string(), 14),
+        ]));
+
+        assert_eq!(check_game(&game, &bag), vec![RuleViolation::ColourLimit {
+            draw: 1,
+            colour: "red".to_string(),
+            count: 20,
+            limit: 12,
+        }]);
+    }
+
+    #[test]
+    fn unknown_colours_and_total_limit_are_violations() {
+        let game = Game::new(1, parse_draws("3 yellow, 4 red; 6 red, 2 blue").unwrap());
+        let bag = BagRules::new(HashMap::from([
+            ("red".to_string(), 6),
+            ("blue".to_string(), 2),
+        ])).with_max_total(7);
+
+        assert_eq!(check_game(&game, &bag), vec![
+            RuleViolation::ColourLimit { draw: 1, colour: "yellow".to_string(), count: 3, limit: 0 },
+            RuleViolation::TotalLimit { required: 11, limit: 7 },
+        ]);
+    }
+
+    #[test]
+    fn repeated_colours_in_a_draw_are_added_up() {
+        let game = Game::new(1, parse_draws("3 red, 2 blue, 4 red; 5 red").unwrap());
+        let bag = BagRules::new(HashMap::from([
+            ("red".to_string(), 5),
+            ("blue".to_string(), 2),
+        ]));
+
+        assert_eq!(game.draws[0].cube_counts(), vec![GameReveal::new("red", 7), GameReveal::new("blue", 2)]);
+        assert_eq!(check_game(&game, &bag), vec![
+            RuleViolation::ColourLimit { draw: 1, colour: "red".to_string(), count: 7, limit: 5 },
         ]);
-        assert_eq!(calculate_possible_games(reveals, available_cubes), vec![1]);
+        assert_eq!(game.minimum_cubes().iter().find(|cube| cube.colour() == "red").map(GameReveal::count), Some(7));
+    }
+
+    #[test]
+    fn keeps_draw_boundaries() {
+        let game = Game::new(1, parse_draws("3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green").unwrap());
+
+        assert_eq!(game.draws.len(), 3);
+        assert_eq!(game.draws[1], Draw { reveals: vec![
+            "1 red".parse::<GameReveal>().unwrap(),
+            "2 green".parse::<GameReveal>().unwrap(),
+            "6 blue".parse::<GameReveal>().unwrap(),
+        ] });
+        assert_eq!(game.reveals().count(), 6);
+    }
+
+    #[test]
+    fn answers_per_draw_questions() {
+        let game = Game::new(1, parse_draws("3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green, 1 green").unwrap());
+
+        assert_eq!(game.max_cubes_drawn(), 9);
+        assert!(game.any_draw_repeats_colour());
+        assert!(!game.draws[1].repeats_colour());
+    }
+
+    #[test]
+    fn draw_probability_without_replacement() {
+        let bag = BagRules::new(HashMap::from([
+            ("red".to_string(), 2),
+            ("blue".to_string(), 3),
+        ]));
+        let game = Game::new(1, parse_draws("1 red, 1 blue; 2 red; 3 red").unwrap());
+
+        let probabilities = game.draw_probabilities(&bag);
+        // 2 * 3 ways out of C(5, 2) = 10, then 1 way out of 10, then impossible
+        assert!((probabilities[0] - 0.6).abs() < 1e-9);
+        assert!((probabilities[1] - 0.1).abs() < 1e-9);
+        assert_eq!(probabilities[2], 0.0);
+    }
+
+    #[test]
+    fn powers_work_on_games() {
+        let games = vec![
+            Game::new(1, parse_draws("3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green").unwrap()),
+            Game::new(3, parse_draws("8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red").unwrap()),
+        ];
+        assert_eq!(caclulate_powers_of_min_games(&games), vec![48, 1560]);
     }
 
     #[test]
@@ -1,3 +1,6 @@
 // src/lib.rs
 
-pub mod island_games;
\ No newline at end of file
+pub mod island_games;
+pub mod bag_rules;
+pub mod game_parser;
+pub mod bag_inference;
\ No newline at end of file
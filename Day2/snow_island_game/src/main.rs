@@ -1,7 +1,11 @@
 use clap::{Arg, App};
-use std::collections::HashMap;
 use std::fs;
-use snow_island_game::island_games::{GameReveal, calculate_possible_games, parse_game_reveals, caclulate_powers_of_min_games};
+use std::process;
+use std::str::FromStr;
+use snow_island_game::bag_inference::{infer_bag, InferenceSettings, Prior};
+use snow_island_game::bag_rules::BagRules;
+use snow_island_game::game_parser::{parse_games, parse_games_lenient};
+use snow_island_game::island_games::{Game, calculate_possible_games, caclulate_powers_of_min_games, explain_impossible_games};
 
 
 fn main() {
@@ -9,66 +13,150 @@ fn main() {
         .version("1.0")
         .author("Mazda Hewitt")
         .about("Calculates possible games from a file based on available stones")
-        .arg(Arg::with_name("red")
-            .help("Number of red stones available")
-            .required(true)
-            .index(1))
-        .arg(Arg::with_name("green")
-            .help("Number of green stones available")
-            .required(true)
-            .index(2))
-        .arg(Arg::with_name("blue")
-            .help("Number of blue stones available")
-            .required(true)
-            .index(3))
+        .arg(Arg::with_name("bag")
+            .short('b')
+            .long("bag")
+            .value_name("BAG")
+            .help("TOML or JSON file describing the cubes in the bag")
+            .takes_value(true)
+            .required(true))
         .arg(Arg::with_name("file")
             .help("File containing game descriptions")
             .required(true)
-            .index(4))
+            .index(1))
+        .arg(Arg::with_name("lenient")
+            .long("lenient")
+            .help("Report every malformed line and carry on with the valid games"))
+        .arg(Arg::with_name("infer")
+            .long("infer")
+            .value_name("GAME")
+            .help("Estimate the bag contents from the draws of a game and judge the --bag claim against it")
+            .takes_value(true))
+        .arg(Arg::with_name("prior")
+            .long("prior")
+            .value_name("PRIOR")
+            .help("Prior on the number of cubes of each colour")
+            .takes_value(true)
+            .possible_values(["uniform", "poisson"])
+            .default_value("uniform"))
+        .arg(Arg::with_name("prior-mean")
+            .long("prior-mean")
+            .value_name("MEAN")
+            .help("Mean cubes per colour for the poisson prior")
+            .takes_value(true)
+            .default_value("12"))
+        .arg(Arg::with_name("max-cubes")
+            .long("max-cubes")
+            .value_name("COUNT")
+            .help("Largest number of cubes of one colour to consider")
+            .takes_value(true)
+            .default_value("30"))
+        .arg(Arg::with_name("confidence")
+            .long("confidence")
+            .value_name("LEVEL")
+            .help("Width of the credible interval for each colour")
+            .takes_value(true)
+            .default_value("0.9"))
         .get_matches();
 
-    let red = matches.value_of("red").unwrap().parse::<u32>().expect("Invalid number for red stones");
-    let green = matches.value_of("green").unwrap().parse::<u32>().expect("Invalid number for green stones");
-    let blue = matches.value_of("blue").unwrap().parse::<u32>().expect("Invalid number for blue stones");
+    let bag = BagRules::from_file(matches.value_of("bag").unwrap()).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
     let filename = matches.value_of("file").unwrap();
 
-    let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
-
-    let games = process_file_contents(&contents);
+    let contents = fs::read_to_string(filename).unwrap_or_else(|e| {
+        eprintln!("could not read {}: {}", filename, e);
+        process::exit(1);
+    });
+
+    let games = process_file_contents(&contents, matches.is_present("lenient"));
+
+    if let Some(game_id) = matches.value_of("infer") {
+        let game_id: u32 = parse_or_exit(game_id, "game id");
+        let game = games.iter().find(|game| game.id == game_id).unwrap_or_else(|| {
+            eprintln!("no game with id {}", game_id);
+            process::exit(1);
+        });
+        let settings = InferenceSettings {
+            prior: match matches.value_of("prior").unwrap() {
+                "poisson" => Prior::Poisson {
+                    mean: parse_or_exit(matches.value_of("prior-mean").unwrap(), "prior mean"),
+                },
+                _ => Prior::Uniform,
+            },
+            max_per_colour: parse_or_exit(matches.value_of("max-cubes").unwrap(), "number for max cubes"),
+            confidence: parse_or_exit(matches.value_of("confidence").unwrap(), "confidence level"),
+        };
+        print_inference(game, &bag, &settings);
+        return;
+    }
 
-    let available_cubes = HashMap::from([
-        ("red".to_string(), red),
-        ("green".to_string(), green),
-        ("blue".to_string(), blue),
-    ]);
+    for (game_id, violations) in explain_impossible_games(&games, &bag) {
+        println!("Game {} is impossible:", game_id);
+        for violation in violations {
+            println!("  {}", violation);
+        }
+    }
 
     // Output the sum of possible game IDs
 
-    let possible_games = calculate_possible_games(games.clone(), available_cubes.clone());
+    let possible_games = calculate_possible_games(&games, &bag);
     // sum possible games
     let sum_possible_games = possible_games.iter().sum::<u32>();
 
-    let sum_min_power_games = caclulate_powers_of_min_games(games.clone());
+    let sum_min_power_games = caclulate_powers_of_min_games(&games);
 
     println!("{} {}", sum_possible_games, sum_min_power_games.iter().sum::<u32>());
 }
 
-fn process_file_contents(contents: &str) -> HashMap<u32, Vec<GameReveal>> {
-    let mut games = HashMap::new();
-
-    for line in contents.lines() {
-        let parts: Vec<&str> = line.splitn(2, ':').collect();
-        if parts.len() != 2 {
-            continue; // Skip lines that do not conform to the expected format
-        }
+fn parse_or_exit<T: FromStr>(value: &str, what: &str) -> T {
+    value.parse().unwrap_or_else(|_| {
+        eprintln!("invalid {}: {}", what, value);
+        process::exit(1);
+    })
+}
 
-        let game_id = parts[0].trim().replace("Game ", "").parse::<u32>().unwrap_or(0);
-        let game_reveals_str = parts[1].trim();
+fn process_file_contents(contents: &str, lenient: bool) -> Vec<Game> {
+    if !lenient {
+        return parse_games(contents).unwrap_or_else(|error| {
+            eprintln!("{}", error.render());
+            process::exit(1);
+        });
+    }
 
-        let game_reveals = parse_game_reveals(game_reveals_str.to_string()).unwrap();
+    let (games, errors) = parse_games_lenient(contents);
+    for error in &errors {
+        eprintln!("{}\n", error.render());
+    }
+    if !errors.is_empty() {
+        eprintln!("skipped {} malformed lines", errors.len());
+    }
+    games
+}
 
-        games.insert(game_id, game_reveals);
+fn print_inference(game: &Game, bag: &BagRules, settings: &InferenceSettings) {
+    let mut colours: Vec<String> = game.reveals()
+        .map(|reveal| reveal.colour().to_string())
+        .chain(bag.colours.keys().cloned())
+        .collect();
+    colours.sort();
+    colours.dedup();
+
+    let estimate = infer_bag(game, &colours, settings).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+
+    println!("Game {}: {} draws, {:?} prior, up to {} cubes per colour", game.id, game.draws.len(), settings.prior, settings.max_per_colour);
+    println!("{:<10} {:>12} {:>8} {:>14}", "colour", "most likely", "mean", format!("{:.0}% interval", settings.confidence * 100.0));
+    for colour in &estimate.colours {
+        println!("{:<10} {:>12} {:>8.2} {:>14}", colour.colour, colour.most_likely, colour.mean, format!("{}..={}", colour.interval.0, colour.interval.1));
     }
 
-    games
-}
\ No newline at end of file
+    let assessment = estimate.assess(bag);
+    println!("claimed bag: posterior {:.3e}, {:.3} times as likely as the most likely bag", assessment.posterior, assessment.relative_to_most_likely);
+    if !assessment.outside_interval.is_empty() {
+        println!("outside the interval for: {}", assessment.outside_interval.join(", "));
+    }
+}
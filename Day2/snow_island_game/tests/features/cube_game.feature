@@ -12,3 +12,31 @@ Feature: Snow Island Cube Game
     And Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
     Then the possible games are "1, 2, 5" and the sum is 8
 
+
+
+  Scenario: Load the whole puzzle file
+    Given a bag with 12 red cubes, 13 green cubes, 14 blue cubes
+    And the game file "test_file.txt"
+    Then there are 0 parse errors
+    And the possible games are "1, 2, 5" and the sum is 8
+    And game 3 is impossible because draw 1 shows 20 red cubes, 8 over the limit
+    And game 4 is impossible because draw 3 shows 15 blue cubes, 1 over the limit
+
+  Scenario: A bag with colours other than red, green and blue
+    Given the bag holds 2 yellow cubes
+    And the bag holds 4 red cubes
+    When Game 1: 1 yellow, 4 red; 2 yellow
+    And Game 2: 3 yellow; 1 red
+    And Game 3: 1 red, 1 purple
+    Then the possible games are "1" and the sum is 1
+    And game 2 is impossible because draw 1 shows 3 yellow cubes, 1 over the limit
+    And game 3 is impossible because draw 1 shows 1 purple cubes, 1 over the limit
+
+  Scenario: A bag with a limit on the total number of cubes
+    Given a bag with 12 red cubes, 13 green cubes, 14 blue cubes
+    And the bag holds at most 20 cubes in total
+    When Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+    And Game 2: 8 green, 6 blue; 4 red, 5 green
+    And Game 3: 10 green, 6 blue; 5 red
+    Then the possible games are "1, 2" and the sum is 3
+    And game 3 is impossible because it needs 21 cubes, 1 over the bag total
@@ -0,0 +1,36 @@
+Feature: Fewest cubes and their power
+  In order to know how small the bag could have been
+  As a player
+  I want the minimum set of cubes for each game and the sum of their powers
+
+  Scenario: Find the minimum set of cubes for each game
+    When Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+    And Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+    And Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+    And Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+    And Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
+    Then the minimum set of cubes for game 1 is "4 red, 2 green, 6 blue"
+    And the minimum set of cubes for game 2 is "1 red, 3 green, 4 blue"
+    And the minimum set of cubes for game 3 is "20 red, 13 green, 6 blue"
+    And the minimum set of cubes for game 4 is "14 red, 3 green, 15 blue"
+    And the minimum set of cubes for game 5 is "6 red, 3 green, 2 blue"
+    And the power of game 1 is 48
+    And the power of game 2 is 12
+    And the power of game 3 is 1560
+    And the power of game 4 is 630
+    And the power of game 5 is 36
+    And the sum of the powers is 2286
+
+  Scenario: Powers with other colours
+    When Game 1: 2 yellow, 1 purple; 3 yellow
+    Then the minimum set of cubes for game 1 is "3 yellow, 1 purple"
+    And the power of game 1 is 3
+
+  Scenario: Sum of powers for a whole puzzle file
+    Given the game file "test_file.txt"
+    Then the sum of the powers is 2286
+
+  Scenario: Sum of powers for the real puzzle input
+    Given the game file "game_input.txt"
+    Then there are 0 parse errors
+    And the sum of the powers is 78669
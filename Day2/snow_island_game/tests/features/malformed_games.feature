@@ -0,0 +1,31 @@
+Feature: Malformed game descriptions
+  In order to fix a broken game file
+  As a player
+  I want to be told where each malformed line goes wrong
+
+  Scenario: A game id that is not a number
+    When the line "Game x: 3 blue" is read
+    Then there are 1 parse errors
+    And there is a parse error on line 1, column 6, expecting "game id"
+
+  Scenario: A reveal without a count
+    When the line "Game 3: 8 green, six blue" is read
+    Then there is a parse error on line 1, column 18, expecting "cube count"
+
+  Scenario: A missing colon after the game id
+    When the line "Game 1 3 blue" is read
+    Then there is a parse error on line 1, column 8, expecting "':' after the game id"
+
+  Scenario: Valid lines are still read around malformed ones
+    When the line "Game 1: 3 blue, 4 red" is read
+    And the line "Game 2: 1 blue, 2" is read
+    Then there are 1 parse errors
+    And there is a parse error on line 2, column 18, expecting "space after the cube count"
+    And the power of game 1 is 12
+
+  Scenario: Every error in a file is collected
+    Given the game file "tests/data/malformed_games.txt"
+    Then there are 2 parse errors
+    And there is a parse error on line 2, column 6, expecting "game id"
+    And there is a parse error on line 3, column 25, expecting "',' or ';' or end of line"
+    And the power of game 4 is 3
@@ -1,6 +1,9 @@
 use cucumber::{given, then, when, World};
 use std::collections::HashMap;
-use snow_island_game::island_games::{GameReveal, parse_game_reveals, calculate_possible_games};
+use std::fs;
+use snow_island_game::bag_rules::{BagRules, RuleViolation};
+use snow_island_game::game_parser::{parse_game_line, parse_games_lenient, ParseError};
+use snow_island_game::island_games::{Game, parse_draws, calculate_possible_games, caclulate_powers_of_min_games, check_game};
 
 
 
@@ -9,7 +12,35 @@ use snow_island_game::island_games::{GameReveal, parse_game_reveals, calculate_p
 #[derive(Debug, Default, World)]
 pub struct CubeGameWorld {
     available_cubes: HashMap<String, u32>,
-    game_reveals: HashMap<u32, Vec<GameReveal>>
+    max_total: Option<u32>,
+    games: Vec<Game>,
+    parse_errors: Vec<ParseError>,
+    lines_read: usize,
+}
+
+impl CubeGameWorld {
+    fn bag(&self) -> BagRules {
+        let bag = BagRules::new(self.available_cubes.clone());
+        match self.max_total {
+            Some(max_total) => bag.with_max_total(max_total),
+            None => bag,
+        }
+    }
+
+    fn game(&self, id: u32) -> &Game {
+        self.games.iter()
+            .find(|game| game.id == id)
+            .unwrap_or_else(|| panic!("no game with id {}", id))
+    }
+}
+
+// Turns "4 red, 2 green, 6 blue" into a colour -> count map
+fn cube_counts(cubes: &str) -> HashMap<String, u32> {
+    parse_draws(cubes).unwrap()
+        .iter()
+        .flat_map(|draw| draw.reveals.iter())
+        .map(|reveal| (reveal.colour().to_string(), reveal.count()))
+        .collect()
 }
 
 // Steps are defined with `given`, `when` and `then` attributes.
@@ -20,27 +51,112 @@ fn bag_with(world: &mut CubeGameWorld, red: u32, green: u32, blue: u32) {
     world.available_cubes.insert("blue".to_string(), blue);
 }
 
-#[when(regex = r"^Game (\d): (.+)$")]
+#[given(expr = "the bag holds {int} {word} cubes")]
+fn bag_holds(world: &mut CubeGameWorld, count: u32, colour: String) {
+    world.available_cubes.insert(colour, count);
+}
+
+#[given(expr = "the bag holds at most {int} cubes in total")]
+fn bag_holds_in_total(world: &mut CubeGameWorld, max_total: u32) {
+    world.max_total = Some(max_total);
+}
+
+#[given(expr = "the game file {string}")]
+fn the_game_file(world: &mut CubeGameWorld, path: String) {
+    let contents = fs::read_to_string(&path).unwrap_or_else(|e| panic!("could not read {}: {}", path, e));
+    let (games, errors) = parse_games_lenient(&contents);
+    world.games.extend(games);
+    world.parse_errors.extend(errors);
+}
+
+#[when(regex = r"^Game (\d+): (.+)$")]
 fn the_game_reveals(world: &mut CubeGameWorld, game: u32, reveals: String){
-    let game_reveals = parse_game_reveals(reveals).unwrap();
+    let draws = parse_draws(&reveals).unwrap();
 
-    world.game_reveals.insert(game, game_reveals);
+    world.games.push(Game::new(game, draws));
 
 }
 
-#[then(regex = r"^the possible games are (.*) and the sum is (\d+)$")]
+#[when(expr = "the line {string} is read")]
+fn the_line_is_read(world: &mut CubeGameWorld, line: String) {
+    world.lines_read += 1;
+    match parse_game_line(&line, world.lines_read) {
+        Ok(game) => world.games.push(game),
+        Err(error) => world.parse_errors.push(error),
+    }
+}
+
+#[then(regex = r#"^the possible games are "(.*)" and the sum is (\d+)$"#)]
 fn calc_possible_games (world: &mut CubeGameWorld, possible_games: String, sum: u32){
-    let possible_games = calculate_possible_games(world.game_reveals.clone(), world.available_cubes.clone());
+    let mut possible_games_found = calculate_possible_games(&world.games, &world.bag());
+    possible_games_found.sort();
+    let expected_games: Vec<u32> = possible_games.split(',')
+        .filter(|id| !id.trim().is_empty())
+        .map(|id| id.trim().parse().unwrap())
+        .collect();
+    assert_eq!(possible_games_found, expected_games);
     // sum possible games
-    let sum_possible_games = possible_games.iter().sum::<u32>();
+    let sum_possible_games = possible_games_found.iter().sum::<u32>();
     // assert that the sum is correct
     assert_eq!(sum_possible_games, sum);
 }
 
+#[then(expr = "the minimum set of cubes for game {int} is {string}")]
+fn minimum_cubes_for_game(world: &mut CubeGameWorld, game: u32, cubes: String) {
+    let minimum: HashMap<String, u32> = world.game(game).minimum_cubes()
+        .iter()
+        .map(|reveal| (reveal.colour().to_string(), reveal.count()))
+        .collect();
+    assert_eq!(minimum, cube_counts(&cubes));
+}
+
+#[then(expr = "the power of game {int} is {int}")]
+fn power_of_game(world: &mut CubeGameWorld, game: u32, power: u32) {
+    let powers = caclulate_powers_of_min_games(std::slice::from_ref(world.game(game)));
+    assert_eq!(powers, vec![power]);
+}
+
+#[then(expr = "the sum of the powers is {int}")]
+fn sum_of_powers(world: &mut CubeGameWorld, sum: u32) {
+    let powers = caclulate_powers_of_min_games(&world.games);
+    assert_eq!(powers.iter().sum::<u32>(), sum);
+}
+
+#[then(expr = "game {int} is impossible because draw {int} shows {int} {word} cubes, {int} over the limit")]
+fn game_is_impossible(world: &mut CubeGameWorld, game: u32, draw: usize, count: u32, colour: String, excess: u32) {
+    let violations = check_game(world.game(game), &world.bag());
+    let found = violations.iter().any(|violation| match violation {
+        RuleViolation::ColourLimit { draw: d, colour: c, count: n, .. } => {
+            *d == draw && *c == colour && *n == count && violation.excess() == excess
+        }
+        RuleViolation::TotalLimit { .. } => false,
+    });
+    assert!(found, "expected a violation in draw {}, got {:?}", draw, violations);
+}
+
+#[then(expr = "game {int} is impossible because it needs {int} cubes, {int} over the bag total")]
+fn game_needs_too_many_cubes(world: &mut CubeGameWorld, game: u32, required: u32, excess: u32) {
+    let violations = check_game(world.game(game), &world.bag());
+    let limit = required - excess;
+    assert!(violations.contains(&RuleViolation::TotalLimit { required, limit }), "got {:?}", violations);
+}
+
+#[then(expr = "there are {int} parse errors")]
+fn parse_error_count(world: &mut CubeGameWorld, count: usize) {
+    assert_eq!(world.parse_errors.len(), count, "errors: {:?}", world.parse_errors);
+}
+
+#[then(expr = "there is a parse error on line {int}, column {int}, expecting {string}")]
+fn parse_error_at(world: &mut CubeGameWorld, line: usize, column: usize, expected: String) {
+    let found = world.parse_errors.iter()
+        .any(|error| error.line == line && error.column == column && error.expected == expected);
+    assert!(found, "no such error in {:?}", world.parse_errors);
+}
+
 // This runs before everything else, so you can setup things here.
 fn main() {
     // You may choose any executor you like (`tokio`, `async-std`, etc.).
     // You may even have an `async` main, it doesn't matter. The point is that
     // Cucumber is composable. :)
     futures::executor::block_on(CubeGameWorld::run("tests/features"));
-}
\ No newline at end of file
+}
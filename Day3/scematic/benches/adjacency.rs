@@ -0,0 +1,40 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use scematic::naive;
+use scematic::schematic::{load_character_grid, Schematic};
+
+// Repeats the puzzle input `times` times across and down
+fn tiled_grid(times: usize) -> Vec<Vec<char>> {
+    let tile = load_character_grid("input_file.txt");
+    (0..times)
+        .flat_map(|_| tile.iter())
+        .map(|row| row.iter().copied().cycle().take(row.len() * times).collect())
+        .collect()
+}
+
+fn bench_adjacency(c: &mut Criterion) {
+    let mut group = c.benchmark_group("part numbers and gear ratios");
+    group.sample_size(10);
+
+    for times in [1, 2, 4, 32] {
+        let grid = tiled_grid(times);
+        let size = grid.len();
+
+        // The naive version takes seconds per run past a few hundred rows
+        if times <= 2 {
+            group.bench_with_input(BenchmarkId::new("naive", size), &grid, |b, grid| {
+                b.iter(|| (naive::sum_of_part_numbers(grid), naive::sum_of_gear_ratios(grid)))
+            });
+        }
+        group.bench_with_input(BenchmarkId::new("indexed", size), &grid, |b, grid| {
+            b.iter(|| {
+                let schematic = Schematic::new(grid.clone());
+                (schematic.sum_of_part_numbers(), schematic.sum_of_gear_ratios())
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_adjacency);
+criterion_main!(benches);
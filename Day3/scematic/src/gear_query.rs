@@ -0,0 +1,192 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use crate::schematic::Schematic;
+
+// How many numbers a symbol has to touch to match a query
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+    Exactly(usize),
+    AtLeast(usize),
+    // Inclusive at both ends
+    Between(usize, usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match *self {
+            Arity::Exactly(n) => count == n,
+            Arity::AtLeast(n) => count >= n,
+            Arity::Between(low, high) => low <= count && count <= high,
+        }
+    }
+}
+
+// Parses "2" (exactly), "3+" (at least) or "2..4" (inclusive range)
+impl FromStr for Arity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |n: &str| n.trim().parse::<usize>().map_err(|_| format!("invalid arity: {}", s));
+
+        if let Some(low) = s.strip_suffix('+') {
+            Ok(Arity::AtLeast(number(low)?))
+        } else if let Some((low, high)) = s.split_once("..") {
+            let (low, high) = (number(low)?, number(high)?);
+            if low > high {
+                return Err(format!("invalid arity: {} is an empty range", s));
+            }
+            Ok(Arity::Between(low, high))
+        } else {
+            Ok(Arity::Exactly(number(s)?))
+        }
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Arity::Exactly(n) => write!(f, "{}", n),
+            Arity::AtLeast(n) => write!(f, "{}+", n),
+            Arity::Between(low, high) => write!(f, "{}..{}", low, high),
+        }
+    }
+}
+
+// How the numbers around a matched symbol are combined
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    Product,
+    Sum,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    // Min and max of no numbers are 0
+    pub fn apply(&self, numbers: &[usize]) -> usize {
+        match self {
+            Aggregate::Product => numbers.iter().product(),
+            Aggregate::Sum => numbers.iter().sum(),
+            Aggregate::Min => numbers.iter().copied().min().unwrap_or(0),
+            Aggregate::Max => numbers.iter().copied().max().unwrap_or(0),
+        }
+    }
+}
+
+impl FromStr for Aggregate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "product" => Ok(Aggregate::Product),
+            "sum" => Ok(Aggregate::Sum),
+            "min" => Ok(Aggregate::Min),
+            "max" => Ok(Aggregate::Max),
+            _ => Err(format!("unknown aggregate: {}", s)),
+        }
+    }
+}
+
+// "Every symbol in `symbols` touching `arity` numbers, combined with `aggregate`"
+#[derive(Debug, Clone, PartialEq)]
+pub struct GearQuery {
+    pub symbols: HashSet<char>,
+    pub arity: Arity,
+    pub aggregate: Aggregate,
+}
+
+impl GearQuery {
+    pub fn new(symbols: &str, arity: Arity, aggregate: Aggregate) -> GearQuery {
+        GearQuery { symbols: symbols.chars().collect(), arity, aggregate }
+    }
+
+    // The puzzle's gears: a '*' next to exactly two numbers, multiplied
+    pub fn gears() -> GearQuery {
+        GearQuery::new("*", Arity::Exactly(2), Aggregate::Product)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolMatch {
+    pub point: (usize, usize),
+    pub symbol: char,
+    // Adjacent numbers in reading order
+    pub numbers: Vec<usize>,
+    pub value: usize,
+}
+
+impl Schematic {
+    // Symbols matching the query in reading order
+    pub fn query(&self, query: &GearQuery) -> Vec<SymbolMatch> {
+        let mut matches = Vec::new();
+
+        for (y, row) in self.grid().iter().enumerate() {
+            for (x, &symbol) in row.iter().enumerate() {
+                if !query.symbols.contains(&symbol) {
+                    continue;
+                }
+                let numbers: Vec<usize> = self.numbers_adjacent_to((x, y)).iter().map(|n| n.number).collect();
+                if query.arity.accepts(numbers.len()) {
+                    let value = query.aggregate.apply(&numbers);
+                    matches.push(SymbolMatch { point: (x, y), symbol, numbers, value });
+                }
+            }
+        }
+
+        matches
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_arity() {
+        assert_eq!("2".parse::<Arity>(), Ok(Arity::Exactly(2)));
+        assert_eq!("3+".parse::<Arity>(), Ok(Arity::AtLeast(3)));
+        assert_eq!("1..2".parse::<Arity>(), Ok(Arity::Between(1, 2)));
+        assert!("3..1".parse::<Arity>().is_err());
+        assert!("two".parse::<Arity>().is_err());
+    }
+
+    #[test]
+    fn test_aggregates() {
+        let numbers = [3, 7, 5];
+        assert_eq!(Aggregate::Product.apply(&numbers), 105);
+        assert_eq!(Aggregate::Sum.apply(&numbers), 15);
+        assert_eq!(Aggregate::Min.apply(&numbers), 3);
+        assert_eq!(Aggregate::Max.apply(&numbers), 7);
+        assert_eq!(Aggregate::Max.apply(&[]), 0);
+    }
+
+    #[test]
+    fn test_gear_query_matches_gear_ratios() {
+        let schematic = Schematic::from_file("test_file.txt").unwrap();
+        let matches = schematic.query(&GearQuery::gears());
+
+        assert_eq!(matches.iter().map(|m| m.point).collect::<Vec<_>>(), vec![(3, 1), (5, 8)]);
+        assert_eq!(matches[0].numbers, vec![467, 35]);
+        assert_eq!(matches.iter().map(|m| m.value).sum::<usize>(), 467835);
+    }
+
+    #[test]
+    fn test_query_other_symbols_and_arities() {
+        let schematic = Schematic::from_reader("\
+1.2.3
+.#.*.
+4.5..
+..$..
+".as_bytes()).unwrap();
+
+        let hashes = schematic.query(&GearQuery::new("#", Arity::AtLeast(3), Aggregate::Sum));
+        assert_eq!(hashes, vec![SymbolMatch { point: (1, 1), symbol: '#', numbers: vec![1, 2, 4, 5], value: 12 }]);
+
+        let single = schematic.query(&GearQuery::new("*$", Arity::Between(1, 3), Aggregate::Max));
+        assert_eq!(single.iter().map(|m| (m.symbol, m.value)).collect::<Vec<_>>(), vec![('*', 5), ('$', 5)]);
+
+        assert!(schematic.query(&GearQuery::new("#", Arity::Exactly(2), Aggregate::Product)).is_empty());
+    }
+}
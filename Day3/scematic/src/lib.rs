@@ -0,0 +1,4 @@
+pub mod schematic;
+pub mod gear_query;
+pub mod naive;
+pub mod render;
@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+use regex::Regex;
+use crate::schematic::{is_symbol, load_symbol_set};
+
+// The original implementation, which keeps a HashSet of adjacent points per number and
+// checks every number against every star. Kept as a reference for tests and benchmarks.
+
+struct NumberLocation {
+    number: usize,
+    adjacent_points: HashSet<(usize, usize)>,
+}
+
+fn find_numbers(grid: &[Vec<char>]) -> Vec<NumberLocation> {
+    let mut numbers = Vec::new();
+    let grid_width = grid.first().map_or(0, |row| row.len());
+    let grid_height = grid.len();
+    let re = Regex::new(r"\d+").unwrap();
+
+    for (row_index, row) in grid.iter().enumerate() {
+        let row_str: String = row.iter().collect();
+        for mat in re.find_iter(&row_str) {
+            let number_start = (mat.start(), row_index);
+            numbers.push(NumberLocation {
+                number: mat.as_str().parse().unwrap(),
+                adjacent_points: adjacent_points_to_number(mat.as_str(), number_start, grid_width, grid_height),
+            });
+        }
+    }
+
+    numbers
+}
+
+fn adjacent_points_to_number(number: &str, point: (usize, usize), grid_width: usize, grid_height: usize) -> HashSet<(usize, usize)> {
+    let num_length = number.len();
+
+    (-1i32..=1)
+        .flat_map(move |d_row| {
+            (-1i32..=1).flat_map(move |d_col| {
+                (0..num_length).filter_map(move |offset| {
+                    let adj_row = point.1 as i32 + d_row;
+                    let adj_col = (point.0 + offset) as i32 + d_col;
+
+                    if adj_row >= 0 && adj_row < grid_height as i32 && adj_col >= 0 && adj_col < grid_width as i32
+                        && !(d_row == 0 && d_col == 0 && offset == 0) {
+                        Some(( adj_col as usize, adj_row as usize))
+                    } else {
+                        None
+                    }
+                })
+            })
+        })
+        .collect()
+}
+
+pub fn sum_of_part_numbers(grid: &[Vec<char>]) -> usize {
+    let numbers = find_numbers(grid);
+    let symbol_set = load_symbol_set(grid, is_symbol);
+
+    numbers.into_iter().filter(|n| {
+        n.adjacent_points.iter().any(|point| symbol_set.contains(point))
+    }).map(|n| n.number).sum()
+}
+
+pub fn sum_of_gear_ratios(grid: &[Vec<char>]) -> usize {
+    let numbers = find_numbers(grid);
+    let star_set = load_symbol_set(grid, |c| c == '*');
+
+    star_set.iter().filter_map(|&star| {
+        let adjacent_numbers: Vec<_> = numbers.iter()
+            .filter(|n| n.adjacent_points.contains(&star))
+            .map(|n| n.number)
+            .collect();
+
+        if adjacent_numbers.len() == 2 {
+            Some(adjacent_numbers[0] * adjacent_numbers[1])
+        } else {
+            None
+        }
+    }).sum()
+}
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use crate::schematic::{Gear, NumberLocation, Schematic};
+
+const RESET: &str = "\x1b[0m";
+const PART_NUMBER: &str = "\x1b[32m";
+const OTHER_NUMBER: &str = "\x1b[31m";
+const SYMBOL: &str = "\x1b[33m";
+const GEAR: &str = "\x1b[1;35m";
+
+// A run of the grid that is highlighted as one piece
+enum Segment<'a> {
+    Number { number: &'a NumberLocation, is_part: bool },
+    Gear(&'a Gear),
+    Symbol(char),
+    Plain(char),
+}
+
+fn segments<'a>(schematic: &'a Schematic, gears: &'a HashMap<(usize, usize), Gear>) -> Vec<Vec<Segment<'a>>> {
+    schematic.grid().iter().enumerate().map(|(y, row)| {
+        let mut line = Vec::new();
+        let mut x = 0;
+        while x < row.len() {
+            if let Some(number) = schematic.number_at((x, y)) {
+                line.push(Segment::Number { number, is_part: schematic.is_part_number(number) });
+                x += number.length;
+                continue;
+            }
+            line.push(match (gears.get(&(x, y)), schematic.symbol_at((x, y))) {
+                (Some(gear), _) => Segment::Gear(gear),
+                (None, Some(symbol)) => Segment::Symbol(symbol),
+                (None, None) => Segment::Plain(row[x]),
+            });
+            x += 1;
+        }
+        line
+    }).collect()
+}
+
+// The number as written in the grid, keeping any leading zeros. Its point and length are
+// in grid cells, so this holds next to symbols outside ASCII.
+fn digits(schematic: &Schematic, number: &NumberLocation) -> String {
+    let (x, y) = number.point;
+    schematic.grid()[y][x..x + number.length].iter().collect()
+}
+
+fn gears_by_point(schematic: &Schematic) -> HashMap<(usize, usize), Gear> {
+    schematic.gears().into_iter().map(|gear| (gear.point, gear)).collect()
+}
+
+// The grid with part numbers in green, other numbers in red, symbols in yellow and gears in bold magenta
+pub fn render_ansi(schematic: &Schematic) -> String {
+    let gears = gears_by_point(schematic);
+    let mut out = String::new();
+
+    for line in segments(schematic, &gears) {
+        for segment in line {
+            match segment {
+                Segment::Number { number, is_part } => {
+                    let colour = if is_part { PART_NUMBER } else { OTHER_NUMBER };
+                    out.push_str(&format!("{}{}{}", colour, digits(schematic, number), RESET));
+                }
+                Segment::Gear(_) => out.push_str(&format!("{}*{}", GEAR, RESET)),
+                Segment::Symbol(symbol) => out.push_str(&format!("{}{}{}", SYMBOL, symbol, RESET)),
+                Segment::Plain(c) => out.push(c),
+            }
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.chars().map(|c| match c {
+        '&' => "&amp;".to_string(),
+        '<' => "&lt;".to_string(),
+        '>' => "&gt;".to_string(),
+        '"' => "&quot;".to_string(),
+        _ => c.to_string(),
+    }).collect()
+}
+
+fn number_tooltip(schematic: &Schematic, number: &NumberLocation) -> String {
+    let symbols: Vec<String> = schematic.adjacent_symbols(number).iter()
+        .map(|((x, y), symbol)| format!("{} at ({}, {})", symbol, x, y))
+        .collect();
+    if symbols.is_empty() {
+        format!("{}: not a part number, no adjacent symbols", number.number)
+    } else {
+        format!("{}: part number, next to {}", number.number, symbols.join(", "))
+    }
+}
+
+// A standalone HTML page of the grid; hovering a number shows its adjacent symbols
+pub fn render_html(schematic: &Schematic) -> String {
+    let gears = gears_by_point(schematic);
+    let mut body = String::new();
+
+    for line in segments(schematic, &gears) {
+        for segment in line {
+            let (class, title, text) = match segment {
+                Segment::Number { number, is_part } => (
+                    if is_part { "part" } else { "other" },
+                    number_tooltip(schematic, number),
+                    digits(schematic, number),
+                ),
+                Segment::Gear(gear) => (
+                    "gear",
+                    format!("gear at ({}, {}): {} x {} = {}", gear.point.0, gear.point.1, gear.numbers.0, gear.numbers.1, gear.ratio()),
+                    "*".to_string(),
+                ),
+                Segment::Symbol(symbol) => ("symbol", String::new(), symbol.to_string()),
+                Segment::Plain(c) => {
+                    body.push_str(&escape_html(&c.to_string()));
+                    continue;
+                }
+            };
+            if title.is_empty() {
+                body.push_str(&format!("<span class=\"{}\">{}</span>", class, escape_html(&text)));
+            } else {
+                body.push_str(&format!("<span class=\"{}\" title=\"{}\">{}</span>", class, escape_html(&title), escape_html(&text)));
+            }
+        }
+        body.push('\n');
+    }
+
+    format!("\
+<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Engine schematic</title>
+<style>
+body {{ background: #0f0f23; color: #666; }}
+pre {{ font-family: monospace; line-height: 1.2; }}
+.part {{ color: #00cc00; }}
+.other {{ color: #ff4444; }}
+.symbol {{ color: #ffff66; }}
+.gear {{ color: #ff66ff; font-weight: bold; }}
+span[title]:hover {{ background: #333355; }}
+</style>
+</head>
+<body>
+<p>Part numbers: {} &middot; Gear ratios: {}</p>
+<pre>
+{}</pre>
+</body>
+</html>
+", schematic.sum_of_part_numbers(), schematic.sum_of_gear_ratios(), body)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_schematic() -> Schematic {
+        Schematic::from_reader("467..114..\n...*......\n..35.#<...\n".as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_render_ansi() {
+        let rendered = render_ansi(&small_schematic());
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        assert_eq!(lines[0], "\x1b[32m467\x1b[0m..\x1b[31m114\x1b[0m..");
+        assert_eq!(lines[1], "...\x1b[1;35m*\x1b[0m......");
+        assert_eq!(lines[2], "..\x1b[32m35\x1b[0m.\x1b[33m#\x1b[0m\x1b[33m<\x1b[0m...");
+    }
+
+    #[test]
+    fn test_render_html() {
+        let html = render_html(&small_schematic());
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<span class=\"part\" title=\"467: part number, next to * at (3, 1)\">467</span>"));
+        assert!(html.contains("<span class=\"other\" title=\"114: not a part number, no adjacent symbols\">114</span>"));
+        assert!(html.contains("title=\"gear at (3, 1): 467 x 35 = 16345\""));
+        assert!(html.contains("<span class=\"symbol\">&lt;</span>"));
+    }
+
+    #[test]
+    fn test_render_multi_byte_symbols() {
+        let schematic = Schematic::from_reader("££12.\n".as_bytes()).unwrap();
+        assert_eq!(render_ansi(&schematic), "\x1b[33m£\x1b[0m\x1b[33m£\x1b[0m\x1b[32m12\x1b[0m.\n");
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use crate::gear_query::GearQuery;
+
+// Points are (x, y), i.e. (column, row), counted from the top left of the grid
+
+// Label for cells that are not part of a number
+const NO_NUMBER: u32 = u32::MAX;
+
+// An engine schematic: a grid of digits, symbols and '.' for empty space
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schematic {
+    grid: Vec<Vec<char>>,
+    numbers: Vec<NumberLocation>,
+    width: usize,
+    // Index into `numbers` for every cell, row by row, so adjacency is a constant time lookup
+    labels: Vec<u32>,
+}
+
+// A '*' next to exactly two part numbers
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gear {
+    pub point: (usize, usize),
+    pub numbers: (usize, usize),
+}
+
+impl Gear {
+    pub fn ratio(&self) -> usize {
+        self.numbers.0 * self.numbers.1
+    }
+}
+
+impl Schematic {
+    // Panics on a number too large for a usize; `try_new` returns the error instead
+    pub fn new(grid: Vec<Vec<char>>) -> Schematic {
+        Schematic::try_new(grid).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(grid: Vec<Vec<char>>) -> Result<Schematic, String> {
+        let numbers = find_numbers(&grid)?;
+        let width = grid.iter().map(|row| row.len()).max().unwrap_or(0);
+
+        let mut labels = vec![NO_NUMBER; width * grid.len()];
+        for (id, number) in numbers.iter().enumerate() {
+            let start = number.point.1 * width + number.point.0;
+            labels[start..start + number.length].fill(id as u32);
+        }
+
+        Ok(Schematic { grid, numbers, width, labels })
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Schematic> {
+        let grid = reader.lines()
+            .map(|line| line.map(|line| line.chars().collect()))
+            .collect::<io::Result<Vec<Vec<char>>>>()?;
+        Schematic::try_new(grid).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Schematic> {
+        Schematic::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn grid(&self) -> &[Vec<char>] {
+        &self.grid
+    }
+
+    pub fn numbers(&self) -> &[NumberLocation] {
+        &self.numbers
+    }
+
+    pub fn symbol_at(&self, point: (usize, usize)) -> Option<char> {
+        self.grid.get(point.1)
+            .and_then(|row| row.get(point.0))
+            .copied()
+            .filter(|&c| is_symbol(c))
+    }
+
+    // The number covering the point, if any
+    pub fn number_at(&self, point: (usize, usize)) -> Option<&NumberLocation> {
+        self.label_at(point).map(|id| &self.numbers[id])
+    }
+
+    fn label_at(&self, (x, y): (usize, usize)) -> Option<usize> {
+        if x >= self.width || y >= self.grid.len() {
+            return None;
+        }
+        match self.labels[y * self.width + x] {
+            NO_NUMBER => None,
+            id => Some(id as usize),
+        }
+    }
+
+    pub fn symbols(&self) -> HashSet<(usize, usize)> {
+        load_symbol_set(&self.grid, is_symbol)
+    }
+
+    // The ring of cells around the number, clipped to the grid
+    pub fn adjacent_points(&self, number: &NumberLocation) -> Vec<(usize, usize)> {
+        let (x, y) = number.point;
+        let (last_x, last_y) = (self.width.saturating_sub(1), self.grid.len().saturating_sub(1));
+        let mut points = Vec::with_capacity(2 * number.length + 6);
+
+        for adj_y in y.saturating_sub(1)..=(y + 1).min(last_y) {
+            for adj_x in x.saturating_sub(1)..=(x + number.length).min(last_x) {
+                if adj_y != y || adj_x < x || adj_x >= x + number.length {
+                    points.push((adj_x, adj_y));
+                }
+            }
+        }
+        points
+    }
+
+    // Symbols touching the number, including diagonally
+    pub fn adjacent_symbols(&self, number: &NumberLocation) -> Vec<((usize, usize), char)> {
+        let mut symbols: Vec<((usize, usize), char)> = self.adjacent_points(number).into_iter()
+            .filter_map(|point| self.symbol_at(point).map(|c| (point, c)))
+            .collect();
+        symbols.sort();
+        symbols
+    }
+
+    // Numbers touching the point, including diagonally, in reading order
+    pub fn numbers_adjacent_to(&self, (x, y): (usize, usize)) -> Vec<&NumberLocation> {
+        let mut ids: Vec<usize> = (y.saturating_sub(1)..=y + 1)
+            .flat_map(|adj_y| (x.saturating_sub(1)..=x + 1).map(move |adj_x| (adj_x, adj_y)))
+            .filter(|&point| point != (x, y))
+            .filter_map(|point| self.label_at(point))
+            .collect();
+        // numbers are labelled in reading order, so sorting the ids sorts the numbers
+        ids.sort_unstable();
+        ids.dedup();
+        ids.into_iter().map(|id| &self.numbers[id]).collect()
+    }
+
+    pub fn is_part_number(&self, number: &NumberLocation) -> bool {
+        self.adjacent_points(number).into_iter().any(|point| self.symbol_at(point).is_some())
+    }
+
+    pub fn part_numbers(&self) -> Vec<&NumberLocation> {
+        self.numbers.iter()
+            .filter(|n| self.is_part_number(n))
+            .collect()
+    }
+
+    pub fn sum_of_part_numbers(&self) -> usize {
+        self.part_numbers().iter().map(|n| n.number).sum()
+    }
+
+    pub fn gears(&self) -> Vec<Gear> {
+        self.query(&GearQuery::gears()).into_iter()
+            .map(|m| Gear { point: m.point, numbers: (m.numbers[0], m.numbers[1]) })
+            .collect()
+    }
+
+    pub fn sum_of_gear_ratios(&self) -> usize {
+        self.gears().iter().map(Gear::ratio).sum()
+    }
+}
+
+pub fn is_symbol(c: char) -> bool {
+    !c.is_numeric() && c != '.'
+}
+
+pub fn load_character_grid(filename: &str) -> Vec<Vec<char>> {
+    fs::read_to_string(filename)
+        .unwrap()
+        .lines()
+        .map(|line| line.chars().collect())
+        .collect()
+}
+pub fn load_symbol_set(grid: &[Vec<char>], is_symbol: fn(char)->bool) -> HashSet<(usize, usize)> {
+    grid.iter().enumerate().flat_map(|(row_index, row)| {
+
+        row.iter().enumerate().filter_map(move |(col_index, &c)| {
+            if is_symbol(c) {
+                Some((col_index, row_index))
+            } else {
+                None
+            }
+        })
+    }).collect()
+}
+
+pub fn calculate_gear_ratios(grid: &[Vec<char>]) -> usize {
+    Schematic::new(grid.to_vec()).sum_of_gear_ratios()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberLocation {
+    pub point: (usize, usize), // start of the number
+    pub number: usize,
+    pub length: usize,
+}
+
+// Fails on a number too large for a usize, giving its 1-based row and column
+pub fn find_numbers(grid: &[Vec<char>]) -> Result<Vec<NumberLocation>, String> {
+    let mut numbers = Vec::new();
+
+    for (row_index, row) in grid.iter().enumerate() {
+        // Columns count chars, not bytes, so symbols outside ASCII take one cell like any other
+        let mut x = 0;
+        while x < row.len() {
+            let length = row[x..].iter().take_while(|c| c.is_ascii_digit()).count();
+            if length == 0 {
+                x += 1;
+                continue;
+            }
+            let digits: String = row[x..x + length].iter().collect();
+            let number = digits.parse::<usize>().map_err(|_| {
+                format!("row {}, column {}: number {} is too large", row_index + 1, x + 1, digits)
+            })?;
+            numbers.push(NumberLocation { point: (x, row_index), number, length });
+            x += length;
+        }
+    }
+
+    Ok(numbers)
+}
+
+pub fn calculate_sum_of_numbers_with_adjacent_symbols(grid: &[Vec<char>]) -> usize {
+    Schematic::new(grid.to_vec()).sum_of_part_numbers()
+}
+
+
+//Tests
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use super::*;
+    use crate::naive;
+
+    #[test]
+    fn test_load_character_grid() {
+        // a two dimensional array of characters
+
+        let grid = load_character_grid("test_file.txt");
+        assert_eq!(grid[0].len(), 10);
+        assert_eq!(grid.len(), 10);
+    }
+
+    #[test]
+    fn test_load_symbol_set() {
+        let points = vec![(3, 1), (6, 3), (3, 4), (5, 5), (3, 8), (5, 8)];
+
+        // Creating a HashSet from the vector of points
+        let symbol_points: HashSet<(usize, usize)> = HashSet::from_iter(points);
+        let character_grid = load_character_grid("test_file.txt");
+        let loaded_points = load_symbol_set(&character_grid, |c| !c.is_numeric() && c != '.');
+        assert_eq!(symbol_points, loaded_points);
+
+    }
+
+
+    #[test]
+    fn test_calculate_sum_of_numbers_with_adjacent_symbols(){
+        let grid = load_character_grid("test_file.txt");
+        let sum = calculate_sum_of_numbers_with_adjacent_symbols(&grid);
+        assert_eq!(sum, 4361);
+    }
+
+    #[test]
+    fn test_calc_gear_ratios(){
+        let grid = load_character_grid("test_file.txt");
+        let gear_ratios = calculate_gear_ratios(&grid);
+        assert_eq!(gear_ratios, 467835);
+    }
+
+    #[test]
+    fn test_schematic_from_reader() {
+        let schematic = Schematic::from_reader("467..114..\n...*......\n..35..633.\n".as_bytes()).unwrap();
+        assert_eq!(schematic.grid().len(), 3);
+        assert_eq!(schematic.numbers().len(), 4);
+        assert_eq!(schematic.numbers()[2], NumberLocation { point: (2, 2), number: 35, length: 2 });
+        assert_eq!(schematic.number_at((3, 2)), Some(&schematic.numbers()[2]));
+        assert_eq!(schematic.number_at((4, 2)), None);
+    }
+
+    #[test]
+    fn test_schematic_part_numbers_and_gears() {
+        let schematic = Schematic::from_file("test_file.txt").unwrap();
+
+        let part_numbers: Vec<usize> = schematic.part_numbers().iter().map(|n| n.number).collect();
+        assert_eq!(part_numbers, vec![467, 35, 633, 617, 592, 755, 664, 598]);
+        assert_eq!(schematic.sum_of_part_numbers(), 4361);
+
+        assert_eq!(schematic.gears(), vec![
+            Gear { point: (3, 1), numbers: (467, 35) },
+            Gear { point: (5, 8), numbers: (755, 598) },
+        ]);
+        assert_eq!(schematic.sum_of_gear_ratios(), 467835);
+    }
+
+    #[test]
+    fn test_schematic_symbol_adjacency() {
+        let schematic = Schematic::from_file("test_file.txt").unwrap();
+
+        let number_617 = schematic.numbers().iter().find(|n| n.number == 617).unwrap();
+        assert_eq!(schematic.adjacent_symbols(number_617), vec![((3, 4), '*')]);
+
+        let number_114 = schematic.numbers().iter().find(|n| n.number == 114).unwrap();
+        assert!(schematic.adjacent_symbols(number_114).is_empty());
+        assert!(!schematic.is_part_number(number_114));
+
+        let around_hash: Vec<usize> = schematic.numbers_adjacent_to((6, 3)).iter().map(|n| n.number).collect();
+        assert_eq!(around_hash, vec![633]);
+
+        let number_467 = &schematic.numbers()[0];
+        let mut ring = schematic.adjacent_points(number_467);
+        ring.sort();
+        assert_eq!(ring, vec![(0, 1), (1, 1), (2, 1), (3, 0), (3, 1)]);
+    }
+
+    #[test]
+    fn test_matches_naive_implementation() {
+        let grid = load_character_grid("input_file.txt");
+        let schematic = Schematic::new(grid.clone());
+
+        assert_eq!(schematic.sum_of_part_numbers(), naive::sum_of_part_numbers(&grid));
+        assert_eq!(schematic.sum_of_gear_ratios(), naive::sum_of_gear_ratios(&grid));
+    }
+
+    #[test]
+    fn test_ragged_rows_do_not_join_numbers() {
+        let schematic = Schematic::from_reader("12\n3*45\n6".as_bytes()).unwrap();
+        let numbers: Vec<usize> = schematic.numbers().iter().map(|n| n.number).collect();
+        assert_eq!(numbers, vec![12, 3, 45, 6]);
+        assert_eq!(schematic.sum_of_part_numbers(), 66);
+        assert!(schematic.gears().is_empty());
+    }
+
+    #[test]
+    fn test_oversized_number_is_an_error() {
+        let error = Schematic::from_reader("1.\n.123456789012345678901234567890*".as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "row 2, column 2: number 123456789012345678901234567890 is too large");
+
+        let largest = usize::MAX.to_string();
+        assert_eq!(Schematic::from_reader(largest.as_bytes()).unwrap().numbers()[0].number, usize::MAX);
+    }
+
+    #[test]
+    fn test_multi_byte_symbols_take_one_column() {
+        let schematic = Schematic::from_reader("..\n££1".as_bytes()).unwrap();
+        assert_eq!(schematic.numbers(), &[NumberLocation { point: (2, 1), number: 1, length: 1 }]);
+        assert_eq!(schematic.sum_of_part_numbers(), 1);
+
+        let schematic = Schematic::from_reader("£.1.".as_bytes()).unwrap();
+        assert_eq!(schematic.numbers(), &[NumberLocation { point: (2, 0), number: 1, length: 1 }]);
+        assert_eq!(schematic.number_at((2, 0)).map(|n| n.number), Some(1));
+        assert_eq!(schematic.sum_of_part_numbers(), 0);
+
+        // Digits from other scripts are not part of a number
+        let schematic = Schematic::from_reader("4٣2*".as_bytes()).unwrap();
+        let numbers: Vec<usize> = schematic.numbers().iter().map(|n| n.number).collect();
+        assert_eq!(numbers, vec![4, 2]);
+    }
+}
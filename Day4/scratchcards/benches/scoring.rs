@@ -0,0 +1,42 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use scratchcards::scratchcard_game::{tally_scratchcards, ScratchCard};
+
+// A deck of `size` cards in the puzzle's layout, 10 winning numbers and 25 of yours from 1..100.
+// A small linear congruential generator keeps the deck the same from run to run.
+fn deck(size: usize) -> Vec<String> {
+    let mut state: u64 = 2023;
+    let mut next = move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 33) % 99 + 1
+    };
+    (1..=size).map(|id| {
+        let winning: Vec<String> = (0..10).map(|_| format!("{:>2}", next())).collect();
+        let yours: Vec<String> = (0..25).map(|_| format!("{:>2}", next())).collect();
+        format!("Card {}: {} | {}", id, winning.join(" "), yours.join(" "))
+    }).collect()
+}
+
+fn bench_scoring(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scoring a deck");
+    group.sample_size(10);
+
+    for size in [10_000, 1_000_000] {
+        let lines = deck(size);
+        let cards: Vec<ScratchCard> = lines.iter().map(|line| ScratchCard::from_string(line).unwrap()).collect();
+
+        group.bench_with_input(BenchmarkId::new("parse", size), &lines, |b, lines| {
+            b.iter(|| lines.iter().map(|line| ScratchCard::from_string(line).unwrap()).collect::<Vec<ScratchCard>>())
+        });
+        group.bench_with_input(BenchmarkId::new("points", size), &cards, |b, cards| {
+            b.iter(|| cards.iter().map(ScratchCard::calculate_points).fold(0u64, u64::saturating_add))
+        });
+        group.bench_with_input(BenchmarkId::new("tally", size), &cards, |b, cards| {
+            b.iter(|| tally_scratchcards(cards))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_scoring);
+criterion_main!(benches);
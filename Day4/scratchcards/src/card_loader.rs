@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead};
+use crate::scratchcard_game::{ScratchCard, ScratchCardError};
+
+// A card that could not be loaded and the 1-based line it was on
+#[derive(Debug, PartialEq, Clone)]
+pub struct LineError {
+    pub line: usize,
+    pub error: ScratchCardError,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+// Loads every card it can, skipping blank lines, and returns the errors for the lines it could not.
+// When a card id repeats, the first card is kept. Only failing to read the input is fatal.
+pub fn load_scratchcards<R: BufRead>(reader: R) -> io::Result<(Vec<ScratchCard>, Vec<LineError>)> {
+    let mut cards = Vec::new();
+    let mut errors = Vec::new();
+    let mut first_seen: HashMap<i32, usize> = HashMap::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_number = index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match ScratchCard::from_string(&line) {
+            Ok(card) => match first_seen.get(&card.scratchcard_number) {
+                Some(&first_line) => errors.push(LineError {
+                    line: line_number,
+                    error: ScratchCardError::DuplicateCardId { scratchcard_number: card.scratchcard_number, first_line },
+                }),
+                None => {
+                    first_seen.insert(card.scratchcard_number, line_number);
+                    cards.push(card);
+                }
+            },
+            Err(error) => errors.push(LineError { line: line_number, error }),
+        }
+    }
+
+    Ok((cards, errors))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_keeps_going_after_errors() {
+        let contents = "\
+Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61   61 30 68 82 17 32 24 19
+
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 1: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 7O 12 93 22 82 36
+";
+        let (cards, errors) = load_scratchcards(contents.as_bytes()).unwrap();
+
+        assert_eq!(cards.iter().map(|card| card.scratchcard_number).collect::<Vec<i32>>(), vec![1, 3]);
+        assert_eq!(errors, vec![
+            LineError { line: 2, error: ScratchCardError::MissingSeparator('|') },
+            LineError { line: 5, error: ScratchCardError::DuplicateCardId { scratchcard_number: 1, first_line: 1 } },
+            LineError { line: 6, error: ScratchCardError::BadNumber { value: "7O".to_string(), column: 32 } },
+        ]);
+        assert_eq!(errors[1].to_string(), "line 5: card 1 was already defined on line 1");
+    }
+
+    #[test]
+    fn test_load_reports_read_errors() {
+        let invalid_utf8: &[u8] = b"Card 1: 1 | 1\n\xff\xfe\n";
+        assert!(load_scratchcards(invalid_utf8).is_err());
+    }
+}
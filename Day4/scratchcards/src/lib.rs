@@ -1 +1,4 @@
 pub mod scratchcard_game;
+pub mod card_loader;
+pub mod rules;
+pub mod number_set;
@@ -1,9 +1,11 @@
-use std::collections::HashMap;
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches};
 use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
-use scratchcards::scratchcard_game::{calc_total_won_scratchacrds, ScratchCard};
+use std::io::BufReader;
+use std::process;
+use std::str::FromStr;
+use scratchcards::card_loader::load_scratchcards;
+use scratchcards::rules::{PrizeRule, Ruleset, ScoringRule};
+use scratchcards::scratchcard_game::{tally_with_rules, CardTally};
 fn main() {
     let matches = App::new("Scratchcard Calculator")
         .version("1.0")
@@ -16,38 +18,123 @@ fn main() {
             .help("Sets the input file to use")
             .takes_value(true)
             .required(true))
+        .arg(Arg::with_name("json")
+            .long("json")
+            .help("Prints the per-card breakdown and totals as JSON"))
+        .arg(Arg::with_name("scoring")
+            .long("scoring")
+            .value_name("RULE")
+            .help("doubling, linear, fibonacci or table:P0,P1,...; repeat to compare rules")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .default_value("doubling"))
+        .arg(Arg::with_name("prizes")
+            .long("prizes")
+            .value_name("RULE")
+            .help("next, previous, wrap or capped:N; repeat to compare rules")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .default_value("next"))
         .get_matches();
 
     let filename = matches.value_of("file").unwrap();
+    let scoring_rules: Vec<ScoringRule> = parse_all(&matches, "scoring");
+    let prize_rules: Vec<PrizeRule> = parse_all(&matches, "prizes");
+    let rulesets: Vec<Ruleset> = scoring_rules.iter()
+        .flat_map(|scoring| prize_rules.iter().map(move |&prizes| Ruleset { scoring: scoring.clone(), prizes }))
+        .collect();
 
-    let mut card_map: HashMap<i32, ScratchCard> = HashMap::new();
-    if let Ok(lines) = read_lines(filename) {
-        let mut total_points = 0;
-        for line in lines.flatten() {
-            let card = ScratchCard::from_string(&line).unwrap();
-            total_points += card.calculate_points();
-            card_map.insert(card.scratchcard_number, card);
-        }
-        println!("number cards: {}", card_map.len());
-        let total_won_cards = calc_total_won_scratchacrds(&card_map);
+    let loaded = File::open(filename).and_then(|file| load_scratchcards(BufReader::new(file)));
+    let (cards, errors) = loaded.unwrap_or_else(|e| {
+        eprintln!("could not read {}: {}", filename, e);
+        process::exit(1);
+    });
+    for error in &errors {
+        eprintln!("{}", error);
+    }
+
+    let tallies: Vec<Vec<CardTally>> = rulesets.iter().map(|rules| tally_with_rules(&cards, rules)).collect();
+
+    if matches.is_present("json") {
+        let reports: Vec<serde_json::Value> = rulesets.iter().zip(&tallies).map(|(rules, tally)| {
+            let (total_points, total_won_cards) = totals(tally);
+            serde_json::json!({
+                "rules": rules.to_string(),
+                "cards": tally,
+                "total_points": total_points,
+                "total_won_cards": total_won_cards,
+            })
+        }).collect();
+        let report = if reports.len() == 1 { reports[0].clone() } else { serde_json::json!({ "rulesets": reports }) };
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else if tallies.len() == 1 {
+        let (total_points, total_won_cards) = totals(&tallies[0]);
+        print_table(&tallies[0]);
+        println!("number cards: {}", tallies[0].len());
         println!("Total points: {}, Total won cards {}", total_points, total_won_cards);
+    } else {
+        print_comparison(&rulesets, &tallies);
     }
+
+    if !errors.is_empty() {
+        process::exit(1);
+    }
+}
+
+fn parse_all<T: FromStr<Err = String>>(matches: &ArgMatches, name: &str) -> Vec<T> {
+    matches.values_of(name).unwrap().map(|value| value.parse().unwrap_or_else(|e: String| {
+        eprintln!("{}", e);
+        process::exit(1);
+    })).collect()
 }
 
-fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
-    where
-        P: AsRef<Path>,
-{
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
+fn totals(tally: &[CardTally]) -> (u64, u64) {
+    tally.iter().fold((0u64, 0u64), |(points, copies), card| {
+        (points.saturating_add(card.points), copies.saturating_add(card.copies))
+    })
 }
 
+fn print_table(tally: &[CardTally]) {
+    println!("{:>6} {:>8} {:>8} {:>10}", "Card", "Matches", "Points", "Copies");
+    for card in tally {
+        println!("{:>6} {:>8} {:>8} {:>10}", card.scratchcard_number, card.matches, card.points, card.copies);
+    }
+}
+
+// One points and copies column pair per ruleset, then the totals for each ruleset
+fn print_comparison(rulesets: &[Ruleset], tallies: &[Vec<CardTally>]) {
+    let names: Vec<String> = rulesets.iter().map(|rules| rules.to_string()).collect();
+    let widths: Vec<usize> = names.iter().map(|name| name.len().max(21)).collect();
+
+    let mut header = format!("{:>6} {:>8}", "", "");
+    let mut columns = format!("{:>6} {:>8}", "Card", "Matches");
+    for (name, width) in names.iter().zip(&widths) {
+        header.push_str(&format!(" | {:^width$}", name, width = width));
+        columns.push_str(&format!(" | {:>10} {:>10}", "Points", "Copies"));
+        columns.push_str(&" ".repeat(width - 21));
+    }
+    println!("{}", header);
+    println!("{}", columns);
 
+    for (row, card) in tallies[0].iter().enumerate() {
+        let mut line = format!("{:>6} {:>8}", card.scratchcard_number, card.matches);
+        for (tally, width) in tallies.iter().zip(&widths) {
+            line.push_str(&format!(" | {:>10} {:>10}", tally[row].points, tally[row].copies));
+            line.push_str(&" ".repeat(width - 21));
+        }
+        println!("{}", line);
+    }
 
+    println!("number cards: {}", tallies[0].len());
+    for (name, tally) in names.iter().zip(tallies) {
+        let (total_points, total_won_cards) = totals(tally);
+        println!("{}: Total points: {}, Total won cards {}", name, total_points, total_won_cards);
+    }
+}
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use scratchcards::scratchcard_game::ScratchCard;
 
     #[test]
     fn test_calculate_points_from_input_line_by_line() {
@@ -61,7 +148,7 @@ mod tests {
 
         let mut total_points = 0;
         for line in fake_file_content.lines() {
-            total_points += ScratchCard::from_string(&line).unwrap().calculate_points();
+            total_points += ScratchCard::from_string(line).unwrap().calculate_points();
         }
 
         assert_eq!(total_points, 13, "Total points should be 13.");
@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+
+// The numbers on one side of a card. Each number counts once however many times it is
+// printed, so a duplicate can never make a card match more than once.
+//
+// Cards almost always use numbers 0-127, which fit in a single u128 so matching two sides is
+// one AND and a popcount. Anything else falls back to a HashSet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumberSet {
+    Small(u128),
+    Large(HashSet<i32>),
+}
+
+impl Default for NumberSet {
+    fn default() -> Self {
+        NumberSet::Small(0)
+    }
+}
+
+impl NumberSet {
+    pub fn from_numbers(numbers: &[i32]) -> NumberSet {
+        if numbers.iter().all(|&n| (0..128).contains(&n)) {
+            NumberSet::Small(numbers.iter().fold(0, |bits, &n| bits | 1 << n))
+        } else {
+            NumberSet::Large(numbers.iter().copied().collect())
+        }
+    }
+
+    pub fn contains(&self, number: i32) -> bool {
+        match self {
+            NumberSet::Small(bits) => (0..128).contains(&number) && bits & (1 << number) != 0,
+            NumberSet::Large(numbers) => numbers.contains(&number),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            NumberSet::Small(bits) => bits.count_ones() as usize,
+            NumberSet::Large(numbers) => numbers.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Distinct numbers in ascending order
+    pub fn to_vec(&self) -> Vec<i32> {
+        match self {
+            NumberSet::Small(bits) => (0..128).filter(|&n| bits & (1 << n) != 0).collect(),
+            NumberSet::Large(numbers) => {
+                let mut numbers: Vec<i32> = numbers.iter().copied().collect();
+                numbers.sort_unstable();
+                numbers
+            }
+        }
+    }
+
+    // How many numbers are in both sets
+    pub fn count_common(&self, other: &NumberSet) -> usize {
+        match (self, other) {
+            (NumberSet::Small(a), NumberSet::Small(b)) => (a & b).count_ones() as usize,
+            (NumberSet::Large(numbers), other) | (other, NumberSet::Large(numbers)) => {
+                numbers.iter().filter(|&&n| other.contains(n)).count()
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_numbers_use_a_bitset() {
+        let set = NumberSet::from_numbers(&[0, 5, 127, 5]);
+        assert_eq!(set, NumberSet::Small(1 | 1 << 5 | 1 << 127));
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(127));
+        assert!(!set.contains(128));
+        assert!(!set.contains(-1));
+        assert_eq!(set.to_vec(), vec![0, 5, 127]);
+    }
+
+    #[test]
+    fn test_large_numbers_fall_back_to_a_hash_set() {
+        let set = NumberSet::from_numbers(&[3, 200, -4]);
+        assert!(matches!(set, NumberSet::Large(_)));
+        assert_eq!(set.to_vec(), vec![-4, 3, 200]);
+    }
+
+    #[test]
+    fn test_count_common_across_representations() {
+        let small = NumberSet::from_numbers(&[1, 2, 3, 4]);
+        let other_small = NumberSet::from_numbers(&[3, 4, 5]);
+        let large = NumberSet::from_numbers(&[2, 3, 1000]);
+
+        assert_eq!(small.count_common(&other_small), 2);
+        assert_eq!(small.count_common(&large), 2);
+        assert_eq!(large.count_common(&small), 2);
+        assert_eq!(large.count_common(&NumberSet::from_numbers(&[1000, 1001])), 1);
+        assert_eq!(small.count_common(&NumberSet::default()), 0);
+    }
+}
@@ -0,0 +1,219 @@
+use std::fmt;
+use std::str::FromStr;
+
+// How many points a card with a given number of matches scores
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScoringRule {
+    // 1 point for the first match, doubled for each match after it
+    Doubling,
+    // 1 point per match
+    Linear,
+    // 1, 1, 2, 3, 5, ... for 1, 2, 3, 4, 5, ... matches
+    Fibonacci,
+    // Points indexed by the number of matches; counts past the end score the last entry
+    Table(Vec<u64>),
+}
+
+impl ScoringRule {
+    pub fn points(&self, matches: usize) -> u64 {
+        if matches == 0 {
+            return match self {
+                ScoringRule::Table(points) => points.first().copied().unwrap_or(0),
+                _ => 0,
+            };
+        }
+        match self {
+            ScoringRule::Doubling => 1u64.checked_shl(matches as u32 - 1).unwrap_or(u64::MAX),
+            ScoringRule::Linear => matches as u64,
+            ScoringRule::Fibonacci => {
+                let (mut previous, mut current) = (0u64, 1u64);
+                for _ in 1..matches {
+                    (previous, current) = (current, previous.saturating_add(current));
+                }
+                current
+            }
+            ScoringRule::Table(points) => points.get(matches).or(points.last()).copied().unwrap_or(0),
+        }
+    }
+}
+
+// Parses "doubling", "linear", "fibonacci" or "table:0,1,3,7"
+impl FromStr for ScoringRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "doubling" => Ok(ScoringRule::Doubling),
+            "linear" => Ok(ScoringRule::Linear),
+            "fibonacci" => Ok(ScoringRule::Fibonacci),
+            _ => match s.strip_prefix("table:") {
+                Some(table) => table.split(',')
+                    .map(|points| points.trim().parse::<u64>().map_err(|_| format!("invalid points in scoring table: {}", points)))
+                    .collect::<Result<Vec<u64>, String>>()
+                    .map(ScoringRule::Table),
+                None => Err(format!("unknown scoring rule: {}", s)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ScoringRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScoringRule::Doubling => write!(f, "doubling"),
+            ScoringRule::Linear => write!(f, "linear"),
+            ScoringRule::Fibonacci => write!(f, "fibonacci"),
+            ScoringRule::Table(points) => {
+                let points: Vec<String> = points.iter().map(|p| p.to_string()).collect();
+                write!(f, "table:{}", points.join(","))
+            }
+        }
+    }
+}
+
+// Which cards a card with N matches wins a copy of. Cards that are not in the deck are never won,
+// and a card never wins a copy of itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrizeRule {
+    // The N cards after it
+    Next,
+    // The N cards before it
+    Previous,
+    // The N cards after it, carrying on from the first card after the last one
+    Wrap,
+    // The next N cards, but never more than the cap
+    Capped(usize),
+}
+
+impl PrizeRule {
+    // Card numbers won by `scratchcard_number` in a deck numbered `first..=last`. The sums are
+    // done in i64 so decks near the ends of i32 don't overflow, and card numbers past them are
+    // never won.
+    pub fn won_cards(&self, scratchcard_number: i32, matches: usize, first: i32, last: i32) -> impl Iterator<Item = i32> {
+        let rule = *self;
+        let (card, first) = (scratchcard_number as i64, first as i64);
+        let matches = i64::try_from(matches).unwrap_or(i64::MAX);
+        let deck_size = last as i64 - first + 1;
+        let count = match rule {
+            PrizeRule::Next | PrizeRule::Previous => matches,
+            PrizeRule::Capped(cap) => matches.min(i64::try_from(cap).unwrap_or(i64::MAX)),
+            PrizeRule::Wrap => matches.min(deck_size - 1),
+        };
+
+        (1..=count)
+            .map(move |offset| match rule {
+                PrizeRule::Next | PrizeRule::Capped(_) => card + offset,
+                PrizeRule::Previous => card - offset,
+                PrizeRule::Wrap => first + (card - first + offset).rem_euclid(deck_size),
+            })
+            // Next and Previous move steadily away from the card, so the first number outside
+            // i32 ends the run
+            .map_while(|won| i32::try_from(won).ok())
+    }
+
+    // Cards are played once each, in this order, and only copies won before a card is played
+    // go on to win more cards
+    pub fn plays_backwards(&self) -> bool {
+        *self == PrizeRule::Previous
+    }
+}
+
+// Parses "next", "previous", "wrap" or "capped:N"
+impl FromStr for PrizeRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "next" => Ok(PrizeRule::Next),
+            "previous" => Ok(PrizeRule::Previous),
+            "wrap" => Ok(PrizeRule::Wrap),
+            _ => match s.strip_prefix("capped:") {
+                Some(cap) => cap.trim().parse::<usize>()
+                    .map(PrizeRule::Capped)
+                    .map_err(|_| format!("invalid cap: {}", cap)),
+                None => Err(format!("unknown prize rule: {}", s)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for PrizeRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PrizeRule::Next => write!(f, "next"),
+            PrizeRule::Previous => write!(f, "previous"),
+            PrizeRule::Wrap => write!(f, "wrap"),
+            PrizeRule::Capped(cap) => write!(f, "capped:{}", cap),
+        }
+    }
+}
+
+// The puzzle's rules are doubling points and copies of the next N cards
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ruleset {
+    pub scoring: ScoringRule,
+    pub prizes: PrizeRule,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Ruleset { scoring: ScoringRule::Doubling, prizes: PrizeRule::Next }
+    }
+}
+
+impl fmt::Display for Ruleset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.scoring, self.prizes)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scoring_rules() {
+        let scores = |rule: ScoringRule| (0..=6).map(|matches| rule.points(matches)).collect::<Vec<u64>>();
+
+        assert_eq!(scores(ScoringRule::Doubling), vec![0, 1, 2, 4, 8, 16, 32]);
+        assert_eq!(scores(ScoringRule::Linear), vec![0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(scores(ScoringRule::Fibonacci), vec![0, 1, 1, 2, 3, 5, 8]);
+        assert_eq!(scores("table:0,10,15".parse().unwrap()), vec![0, 10, 15, 15, 15, 15, 15]);
+        assert_eq!(ScoringRule::Doubling.points(100), u64::MAX);
+    }
+
+    #[test]
+    fn test_prize_rules() {
+        let won = |rule: PrizeRule, card: i32, matches: usize| rule.won_cards(card, matches, 1, 6).collect::<Vec<i32>>();
+
+        assert_eq!(won(PrizeRule::Next, 2, 3), vec![3, 4, 5]);
+        assert_eq!(won(PrizeRule::Previous, 4, 2), vec![3, 2]);
+        assert_eq!(won(PrizeRule::Capped(2), 2, 3), vec![3, 4]);
+        assert_eq!(won(PrizeRule::Wrap, 5, 3), vec![6, 1, 2]);
+        assert_eq!(won(PrizeRule::Wrap, 5, 10), vec![6, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_prize_rules_at_the_ends_of_i32() {
+        let (min, max) = (i32::MIN, i32::MAX);
+        let won = |rule: PrizeRule, card: i32, matches: usize| rule.won_cards(card, matches, min, max).collect::<Vec<i32>>();
+
+        assert!(won(PrizeRule::Next, max, 1).is_empty());
+        assert_eq!(won(PrizeRule::Next, max - 1, 3), vec![max]);
+        assert_eq!(won(PrizeRule::Capped(usize::MAX), max - 1, usize::MAX), vec![max]);
+        assert_eq!(won(PrizeRule::Previous, min + 1, 3), vec![min]);
+        assert_eq!(won(PrizeRule::Wrap, max, 2), vec![min, min + 1]);
+        assert_eq!(won(PrizeRule::Wrap, min, 1), vec![min + 1]);
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        assert_eq!("fibonacci".parse::<ScoringRule>(), Ok(ScoringRule::Fibonacci));
+        assert_eq!("table:1, 2".parse::<ScoringRule>(), Ok(ScoringRule::Table(vec![1, 2])));
+        assert!("table:1,x".parse::<ScoringRule>().is_err());
+        assert_eq!("capped:3".parse::<PrizeRule>(), Ok(PrizeRule::Capped(3)));
+        assert!("sideways".parse::<PrizeRule>().is_err());
+        assert_eq!(Ruleset { scoring: ScoringRule::Table(vec![0, 5]), prizes: PrizeRule::Capped(2) }.to_string(), "table:0,5/capped:2");
+    }
+}
@@ -1,125 +1,187 @@
 use std::collections::HashMap;
-use std::collections::HashSet;
+use std::fmt;
+use serde::Serialize;
+use crate::number_set::NumberSet;
+use crate::rules::{Ruleset, ScoringRule};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ScratchCardError {
+    // The ':' after the card id or the '|' between the two lists is missing
+    MissingSeparator(char),
+    UnexpectedSeparator { separator: char, column: usize },
+    BadCardId(String),
+    // `column` is the 1-based column where the number starts
+    BadNumber { value: String, column: usize },
+    DuplicateCardId { scratchcard_number: i32, first_line: usize },
+}
+
+impl fmt::Display for ScratchCardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScratchCardError::MissingSeparator(separator) => write!(f, "missing '{}'", separator),
+            ScratchCardError::UnexpectedSeparator { separator, column } => {
+                write!(f, "unexpected '{}' at column {}", separator, column)
+            }
+            ScratchCardError::BadCardId(id) => write!(f, "invalid card id \"{}\"", id),
+            ScratchCardError::BadNumber { value, column } => {
+                write!(f, "invalid number \"{}\" at column {}", value, column)
+            }
+            ScratchCardError::DuplicateCardId { scratchcard_number, first_line } => {
+                write!(f, "card {} was already defined on line {}", scratchcard_number, first_line)
+            }
+        }
+    }
+}
+
+// Repeated numbers on either side count once; see `NumberSet`
 #[derive(Debug, Default, Clone)]
 pub struct ScratchCard {
     pub scratchcard_number: i32,
-    pub your_numbers: Vec<i32>,
-    pub winning_numbers: Vec<i32>,
+    pub your_numbers: NumberSet,
+    pub winning_numbers: NumberSet,
 }
 
 impl ScratchCard {
     // Constructor for creating a new ScratchCard
-    fn new(scratchcard_number: i32, your_numbers: Vec<i32>, winning_numbers: Vec<i32>) -> Self {
+    pub fn new(scratchcard_number: i32, your_numbers: Vec<i32>, winning_numbers: Vec<i32>) -> Self {
 
         ScratchCard {
             scratchcard_number,
-            your_numbers,
-            winning_numbers
+            your_numbers: NumberSet::from_numbers(&your_numbers),
+            winning_numbers: NumberSet::from_numbers(&winning_numbers),
         }
 
     }
 
-    pub fn from_string(line: &str) -> Result<ScratchCard, String> {
-        let parts: Vec<&str> = line.split("|").collect();
-        if parts.len() != 2 {
-            return Err("Invalid format".to_string());
-        }
-
-        let left_side = parts[0].trim();
-        let right_side = parts[1].trim();
-
-        let left_parts: Vec<&str> = left_side.split(":").collect();
-        if left_parts.len() != 2 {
-            return Err("Invalid format on the left side of '|'".to_string());
+    pub fn from_string(line: &str) -> Result<ScratchCard, ScratchCardError> {
+        let (left_side, right_side) = line.split_once('|')
+            .ok_or(ScratchCardError::MissingSeparator('|'))?;
+        if let Some(extra) = right_side.find('|') {
+            let column = left_side.len() + 1 + extra + 1;
+            return Err(ScratchCardError::UnexpectedSeparator { separator: '|', column });
         }
 
-        let scratchcard_number = left_parts[0].trim()
-            .split_whitespace().last() // Get the last word in the "Card X" part
-            .ok_or("Invalid scratchcard number".to_string())?
-            .parse::<i32>()
-            .map_err(|_| "Invalid scratchcard number".to_string())?;
+        let (card_label, winning_side) = left_side.split_once(':')
+            .ok_or(ScratchCardError::MissingSeparator(':'))?;
 
-        let winning_numbers = left_parts[1].trim().split_whitespace()
-            .map(|num| num.parse::<i32>())
-            .collect::<Result<Vec<i32>, _>>()
-            .map_err(|_| "Invalid winning number".to_string())?;
+        let card_id = card_label.split_whitespace().last().unwrap_or(""); // Get the last word in the "Card X" part
+        let scratchcard_number = card_id.parse::<i32>()
+            .map_err(|_| ScratchCardError::BadCardId(card_id.to_string()))?;
 
-        let your_numbers = right_side.split_whitespace()
-            .map(|num| num.parse::<i32>())
-            .collect::<Result<Vec<i32>, _>>()
-            .map_err(|_| "Invalid player number".to_string())?;
+        let winning_numbers = parse_numbers(winning_side, card_label.len() + 1)?;
+        let your_numbers = parse_numbers(right_side, left_side.len() + 1)?;
 
-        Ok(ScratchCard {
-            scratchcard_number,
-            winning_numbers,
-            your_numbers,
-        })
+        Ok(ScratchCard::new(scratchcard_number, your_numbers, winning_numbers))
     }
 
     // Method to calculate the points for this scratchcard, given a set of winning numbers
-    pub fn calculate_points(&self) -> i32 {
-        let mut first_match = true;
-
-        self.your_numbers.iter()
-            .filter(|num| self.winning_numbers.contains(num))
-            .fold(0, |mut acc, _| {
-                if first_match {
-                    acc = 1;
-                    first_match = false;
-                } else {
-                    acc *= 2;
-                }
-                acc
-            })
+    pub fn calculate_points(&self) -> u64 {
+        ScoringRule::Doubling.points(self.matches())
+    }
+
+    pub fn matches(&self) -> usize {
+        self.your_numbers.count_common(&self.winning_numbers)
     }
 
     pub fn won_cards(&self) -> Vec<i32> {
-        let num_matches = count_matches(&&self.your_numbers, &&self.winning_numbers);
+        let num_matches = self.matches();
         //return a vector which contains numbers starting at the card number pls 1 to the card number plus num matches plus 1
         let mut won_cards: Vec<i32> = Vec::new();
         for i  in self.scratchcard_number+1..self.scratchcard_number + num_matches as i32 + 1 {
-            won_cards.push(i as i32);
+            won_cards.push(i);
         }
         won_cards
     }
 }
 
 
-fn count_matches(list1: &Vec<i32>, list2: &Vec<i32>) -> usize {
-    let mut count = 0;
-
-    for num1 in list1 {
-        for num2 in list2 {
-            if num1 == num2 {
-                count += 1;
+// Parses whitespace separated numbers; `offset` is the byte offset of `text` in its line
+fn parse_numbers(text: &str, offset: usize) -> Result<Vec<i32>, ScratchCardError> {
+    let mut numbers = Vec::new();
+    let mut start = None;
+
+    for (index, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        match (c.is_whitespace(), start) {
+            (true, Some(first)) => {
+                let value = &text[first..index];
+                numbers.push(value.parse::<i32>().map_err(|_| ScratchCardError::BadNumber {
+                    value: value.to_string(),
+                    column: offset + first + 1,
+                })?);
+                start = None;
             }
+            (false, None) => start = Some(index),
+            _ => {}
         }
     }
 
-    count
+    Ok(numbers)
 }
 
-pub fn calc_total_won_scratchacrds(original_cards: &HashMap<i32, ScratchCard>) -> i32 {
-    let mut total_won_cards = 0;
-    let mut cards_to_process: HashMap<i32, i32> = original_cards.keys().map(|&k| (k, 1)).collect();
-    let mut next_batch = HashMap::new();
+// What one card is worth and how many copies of it end up being scratched
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CardTally {
+    pub scratchcard_number: i32,
+    pub matches: usize,
+    pub points: u64,
+    // The original card plus every copy won from other cards
+    pub copies: u64,
+}
 
-    while !cards_to_process.is_empty() {
-        for (&card_number, &count) in &cards_to_process {
-            if let Some(card) = original_cards.get(&card_number) {
-                total_won_cards += count;
+// Under the puzzle's rules cards only ever win copies of later cards, so a single pass in
+// card order sees every copy of a card before it has to hand on that card's winnings
+pub fn tally_scratchcards(cards: &[ScratchCard]) -> Vec<CardTally> {
+    tally_with_rules(cards, &Ruleset::default())
+}
 
-                for won_card_number in card.won_cards() {
-                    *next_batch.entry(won_card_number).or_insert(0) += count;
-                }
-            }
+// Tallies the deck in card number order; see `PrizeRule::plays_backwards` for the play order
+pub fn tally_with_rules(cards: &[ScratchCard], rules: &Ruleset) -> Vec<CardTally> {
+    let mut sorted: Vec<&ScratchCard> = cards.iter().collect();
+    sorted.sort_by_key(|card| card.scratchcard_number);
+    let (first, last) = match (sorted.first(), sorted.last()) {
+        (Some(first), Some(last)) => (first.scratchcard_number, last.scratchcard_number),
+        _ => return Vec::new(),
+    };
+
+    let numbers: Vec<i32> = sorted.iter().map(|card| card.scratchcard_number).collect();
+    let matches: Vec<usize> = sorted.iter().map(|card| card.matches()).collect();
+    let mut copies = vec![1u64; sorted.len()];
+
+    // Decks are usually numbered without gaps, so try the card `won - number` places along
+    // before falling back to a binary search
+    let position = |index: usize, won: i32| -> Option<usize> {
+        let guess = index as i64 + (won as i64 - numbers[index] as i64);
+        if guess >= 0 && numbers.get(guess as usize) == Some(&won) {
+            Some(guess as usize)
+        } else {
+            numbers.binary_search(&won).ok()
         }
+    };
 
-        std::mem::swap(&mut cards_to_process, &mut next_batch);
-        next_batch.clear();
+    let play = |index: usize, copies: &mut Vec<u64>| {
+        for won_card_number in rules.prizes.won_cards(numbers[index], matches[index], first, last) {
+            if let Some(won) = position(index, won_card_number) {
+                copies[won] = copies[won].saturating_add(copies[index]);
+            }
+        }
+    };
+    if rules.prizes.plays_backwards() {
+        (0..sorted.len()).rev().for_each(|index| play(index, &mut copies));
+    } else {
+        (0..sorted.len()).for_each(|index| play(index, &mut copies));
     }
 
-    total_won_cards
+    sorted.iter().zip(matches).zip(copies).map(|((card, matches), copies)| CardTally {
+        scratchcard_number: card.scratchcard_number,
+        matches,
+        points: rules.scoring.points(matches),
+        copies,
+    }).collect()
+}
+
+pub fn calc_total_won_scratchacrds(original_cards: &HashMap<i32, ScratchCard>) -> u64 {
+    let cards: Vec<ScratchCard> = original_cards.values().cloned().collect();
+    tally_scratchcards(&cards).iter().fold(0u64, |total, tally| total.saturating_add(tally.copies))
 }
 
 
@@ -127,7 +189,6 @@ pub fn calc_total_won_scratchacrds(original_cards: &HashMap<i32, ScratchCard>) -
 
 #[cfg(test)]
 mod tests {
-    use std::collections::hash_map::Iter;
     use super::*;
     #[test]
     fn test_calculate_points() {
@@ -139,9 +200,6 @@ mod tests {
 
     #[test]
     fn test_scratchcard_witn_no_winning_numers() {
-        // Winning numbers are on the left side of '|'
-        let winning_numbers = vec![87, 83, 26, 28, 32];
-
         // Scratchcard numbers are on the right side of '|'
         let scratchcard = ScratchCard::from_string("Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11").unwrap();
 
@@ -174,8 +232,8 @@ mod tests {
     fn test_won_cards() {
         let scratch_card = ScratchCard {
             scratchcard_number: 1,
-            your_numbers: vec![83, 86, 6, 31, 17],
-            winning_numbers: vec![83, 86, 6, 31, 17, 9, 48, 53]
+            your_numbers: NumberSet::from_numbers(&[83, 86, 6, 31, 17]),
+            winning_numbers: NumberSet::from_numbers(&[83, 86, 6, 31, 17, 9, 48, 53])
         };
 
         let won_card_numbers = scratch_card.won_cards();
@@ -183,6 +241,109 @@ mod tests {
         assert_eq!(won_card_numbers, vec![2i32, 3i32, 4i32, 5i32, 6i32]);
     }
 
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(ScratchCard::from_string("Card 1: 41 48 83 86 17").unwrap_err(), ScratchCardError::MissingSeparator('|'));
+        assert_eq!(ScratchCard::from_string("Card 1 41 48 | 83 86").unwrap_err(), ScratchCardError::MissingSeparator(':'));
+        assert_eq!(ScratchCard::from_string("Card x: 41 48 | 83 86").unwrap_err(), ScratchCardError::BadCardId("x".to_string()));
+        assert_eq!(ScratchCard::from_string("Card 1: 41 48 | 83 | 86").unwrap_err(),
+                   ScratchCardError::UnexpectedSeparator { separator: '|', column: 20 });
+
+        let error = ScratchCard::from_string("Card 1: 41 4a | 83 86").unwrap_err();
+        assert_eq!(error, ScratchCardError::BadNumber { value: "4a".to_string(), column: 12 });
+        assert_eq!(error.to_string(), "invalid number \"4a\" at column 12");
+
+        let error = ScratchCard::from_string("Card 1: 41 48 | 83  -").unwrap_err();
+        assert_eq!(error, ScratchCardError::BadNumber { value: "-".to_string(), column: 21 });
+    }
+
+    #[test]
+    fn test_duplicate_numbers_match_once() {
+        let card = ScratchCard::from_string("Card 1: 5 5 7 | 5 5 5 7 9").unwrap();
+        assert_eq!(card.matches(), 2);
+        assert_eq!(card.calculate_points(), 2);
+
+        let card = ScratchCard::from_string("Card 2: 500 5 | 5 500 500").unwrap();
+        assert_eq!(card.matches(), 2);
+    }
+
+    #[test]
+    fn test_points_do_not_overflow() {
+        let numbers: Vec<i32> = (0..70).collect();
+        let points = |matches: usize| ScratchCard::new(1, numbers[..matches].to_vec(), numbers.clone()).calculate_points();
+
+        assert_eq!(points(32), 1 << 31);
+        assert_eq!(points(33), 1 << 32);
+        assert_eq!(points(64), 1 << 63);
+        assert_eq!(points(70), u64::MAX);
+    }
+
+    #[test]
+    fn test_tally_scratchcards() {
+        let cards: Vec<ScratchCard> = vec![
+            "Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83",
+            "Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19",
+            "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53",
+            "Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1",
+            "Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11",
+            "Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36",
+        ].into_iter().map(|line| ScratchCard::from_string(line).unwrap()).collect();
+
+        let tally = tally_scratchcards(&cards);
+        assert_eq!(tally.iter().map(|t| t.scratchcard_number).collect::<Vec<i32>>(), vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(tally.iter().map(|t| t.copies).collect::<Vec<u64>>(), vec![1, 2, 4, 8, 14, 1]);
+        assert_eq!(tally.iter().map(|t| t.points).sum::<u64>(), 13);
+        assert_eq!(tally[0], CardTally { scratchcard_number: 1, matches: 4, points: 8, copies: 1 });
+    }
+
+    #[test]
+    fn test_winnings_past_the_last_card_are_ignored() {
+        let cards = vec![
+            ScratchCard::new(1, vec![1, 2, 3], vec![1, 2, 3]),
+            ScratchCard::new(2, vec![4], vec![4]),
+        ];
+        let copies: Vec<u64> = tally_scratchcards(&cards).iter().map(|t| t.copies).collect();
+        assert_eq!(copies, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_tally_at_the_ends_of_i32() {
+        let cards = vec![
+            ScratchCard::new(i32::MIN, vec![1], vec![1]),
+            ScratchCard::new(i32::MAX - 1, vec![1, 2], vec![1, 2]),
+            ScratchCard::new(i32::MAX, vec![1], vec![1]),
+        ];
+        let copies = |prizes: &str| {
+            let rules = Ruleset { prizes: prizes.parse().unwrap(), ..Ruleset::default() };
+            tally_with_rules(&cards, &rules).iter().map(|t| t.copies).collect::<Vec<u64>>()
+        };
+
+        assert_eq!(copies("next"), vec![1, 1, 2]);
+        // Cards wrap round from i32::MAX to i32::MIN, 2^32 - 1 places away
+        assert_eq!(copies("wrap"), vec![4, 1, 2]);
+        assert_eq!(copies("previous"), vec![1, 2, 1]);
+    }
+
+    #[test]
+    fn test_tally_with_other_rules() {
+        let cards = vec![
+            ScratchCard::new(1, vec![1, 2], vec![1, 2]),
+            ScratchCard::new(2, vec![4], vec![4]),
+            ScratchCard::new(3, vec![5, 6, 7], vec![5, 6, 7]),
+        ];
+        let tally = |scoring: &str, prizes: &str| {
+            let rules = Ruleset { scoring: scoring.parse().unwrap(), prizes: prizes.parse().unwrap() };
+            tally_with_rules(&cards, &rules).iter().map(|t| (t.points, t.copies)).collect::<Vec<(u64, u64)>>()
+        };
+
+        assert_eq!(tally("doubling", "next"), vec![(2, 1), (1, 2), (4, 4)]);
+        assert_eq!(tally("linear", "capped:1"), vec![(2, 1), (1, 2), (3, 3)]);
+        // played from the last card back to the first
+        assert_eq!(tally("fibonacci", "previous"), vec![(1, 4), (1, 2), (2, 1)]);
+        // card 3 wins cards 1 and 2 after they have been played, so those copies win nothing
+        assert_eq!(tally("table:0,5", "wrap"), vec![(5, 5), (5, 6), (5, 4)]);
+    }
+
 
 
 }
\ No newline at end of file
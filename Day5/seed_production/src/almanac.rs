@@ -0,0 +1,508 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use crate::garden_mapper::{GardenMapper, ListedRange, OverlapPolicy, RangeMapper, SeedRange, SeedTrace, TraceStep, STAGE_CATEGORIES};
+
+// One "X-to-Y map:" section
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryMap {
+    pub source: String,
+    pub destination: String,
+    // Line of the header, 1-based
+    pub line: usize,
+    // (dest_start, src_start, length) and the line each range was on
+    pub ranges: Vec<((i64, i64, i64), usize)>,
+}
+
+impl CategoryMap {
+    pub fn name(&self) -> String {
+        format!("{}-to-{}", self.source, self.destination)
+    }
+
+    pub fn mapper(&self, policy: OverlapPolicy) -> Result<RangeMapper, String> {
+        RangeMapper::from_vec_with_policy(self.ranges.iter().map(|(range, _)| *range).collect(), policy)
+            .map_err(|e| format!("{} map: {}", self.name(), e))
+    }
+
+    // The range, as listed, that maps `value` under `policy`
+    pub fn matching_range(&self, value: i64, policy: OverlapPolicy) -> Option<ListedRange> {
+        self.ranges.iter().enumerate()
+            .map(|(index, &((dest_start, src_start, length), line))| {
+                (index, ListedRange { range: SeedRange { dest_start, src_start, length }, line })
+            })
+            .filter(|(_, listed)| listed.range.contains(value))
+            .min_by_key(|(index, listed)| policy.rank(*index, &listed.range))
+            .map(|(_, listed)| listed)
+    }
+}
+
+// Something wrong, or worth knowing, about an almanac. Lines are 1-based.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlmanacIssue {
+    MissingSeeds,
+    MalformedLine { line: usize, text: String, reason: String },
+    // A range line before the first map header
+    RangeOutsideMap { line: usize },
+    DuplicateMap { line: usize, name: String, first_line: usize },
+    // Two source ranges in the same map share values; `policy` says which one applies
+    OverlappingRanges { map: String, line: usize, other_line: usize, policy: OverlapPolicy },
+    // Values between two source ranges that no range covers, so they map to themselves
+    Gap { map: String, start: i64, end: i64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl AlmanacIssue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            AlmanacIssue::OverlappingRanges { policy: OverlapPolicy::Reject, .. } => Severity::Error,
+            AlmanacIssue::OverlappingRanges { .. } | AlmanacIssue::Gap { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for AlmanacIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AlmanacIssue::MissingSeeds => write!(f, "error: no \"seeds:\" line"),
+            AlmanacIssue::MalformedLine { line, text, reason } => {
+                write!(f, "error: line {}: {}: \"{}\"", line, reason, text)
+            }
+            AlmanacIssue::RangeOutsideMap { line } => {
+                write!(f, "error: line {}: range before any \"X-to-Y map:\" header", line)
+            }
+            AlmanacIssue::DuplicateMap { line, name, first_line } => {
+                write!(f, "error: line {}: {} map was already defined on line {}", line, name, first_line)
+            }
+            AlmanacIssue::OverlappingRanges { map, line, other_line, policy } => {
+                let label = if self.severity() == Severity::Error { "error" } else { "warning" };
+                write!(f, "{}: line {}: {} source range overlaps the range on line {}", label, line, map, other_line)?;
+                match policy {
+                    OverlapPolicy::Reject => Ok(()),
+                    OverlapPolicy::FirstWins => write!(f, "; the range listed first is used"),
+                    OverlapPolicy::LastWins => write!(f, "; the range listed last is used"),
+                    OverlapPolicy::Split => write!(f, "; the shorter range is used"),
+                }
+            }
+            AlmanacIssue::Gap { map, start, end } => {
+                write!(f, "warning: {}: values {}..{} are not covered and map to themselves", map, start, end)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Almanac {
+    pub seeds: Vec<i64>,
+    pub maps: Vec<CategoryMap>,
+    pub warnings: Vec<AlmanacIssue>,
+    // Which range maps values that more than one range in a map covers
+    pub overlap_policy: OverlapPolicy,
+}
+
+// Parses "dest_start src_start length"
+pub fn parse_range_line(line: &str) -> Result<(i64, i64, i64), String> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() != 3 {
+        return Err(format!("expected 3 numbers, found {}", parts.len()));
+    }
+    let numbers = parts.iter()
+        .map(|part| part.parse::<i64>().map_err(|_| format!("invalid number \"{}\"", part)))
+        .collect::<Result<Vec<i64>, String>>()?;
+    if numbers[2] < 0 {
+        return Err("negative range length".to_string());
+    }
+    Ok((numbers[0], numbers[1], numbers[2]))
+}
+
+// "seed-to-soil map:" -> ("seed", "soil")
+fn parse_header(line: &str) -> Option<Result<(String, String), String>> {
+    let name = line.trim().strip_suffix("map:")?.trim_end();
+    let valid = |category: &str| !category.is_empty() && category.chars().all(|c| c.is_alphanumeric() || c == '_');
+    Some(match name.split_once("-to-") {
+        Some((source, destination)) if valid(source) && valid(destination) => {
+            Ok((source.to_string(), destination.to_string()))
+        }
+        _ => Err("expected a header like \"seed-to-soil map:\"".to_string()),
+    })
+}
+
+// Warnings for overlapping and uncovered source values between a map's ranges
+fn check_ranges(map: &CategoryMap, policy: OverlapPolicy) -> Vec<AlmanacIssue> {
+    let mut ranges = map.ranges.clone();
+    ranges.sort_by_key(|((_, src_start, _), _)| *src_start);
+
+    let mut issues = vec![];
+    // The range reaching furthest so far and its line
+    let mut furthest: Option<(i64, usize)> = None;
+    for ((_, src_start, length), line) in ranges {
+        match furthest {
+            Some((end, other_line)) if end > src_start => {
+                issues.push(AlmanacIssue::OverlappingRanges { map: map.name(), line, other_line, policy });
+            }
+            Some((end, _)) if end < src_start => {
+                issues.push(AlmanacIssue::Gap { map: map.name(), start: end, end: src_start });
+            }
+            _ => {}
+        }
+        if furthest.is_none_or(|(end, _)| src_start + length > end) {
+            furthest = Some((src_start + length, line));
+        }
+    }
+    issues
+}
+
+// Reads a "seeds:" line followed by any number of "X-to-Y map:" sections in any order.
+// Blank lines are ignored. Where ranges in a map overlap the one listed first wins.
+pub fn parse_almanac(contents: &str) -> Result<Almanac, Vec<AlmanacIssue>> {
+    parse_almanac_with_policy(contents, OverlapPolicy::FirstWins)
+}
+
+// As `parse_almanac`, resolving overlapping ranges with `policy`. Overlaps are errors under
+// OverlapPolicy::Reject and warnings otherwise. On failure every issue found is returned,
+// warnings included.
+pub fn parse_almanac_with_policy(contents: &str, policy: OverlapPolicy) -> Result<Almanac, Vec<AlmanacIssue>> {
+    let mut seeds = None;
+    let mut maps: Vec<CategoryMap> = vec![];
+    let mut issues = vec![];
+    let mut first_defined: HashMap<String, usize> = HashMap::new();
+
+    for (index, text) in contents.lines().enumerate() {
+        let line = index + 1;
+        let trimmed = text.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let malformed = |reason: String| AlmanacIssue::MalformedLine { line, text: text.to_string(), reason };
+
+        if let Some(numbers) = trimmed.strip_prefix("seeds:") {
+            match numbers.split_whitespace().map(|n| n.parse::<i64>()).collect::<Result<Vec<i64>, _>>() {
+                Ok(numbers) if seeds.is_none() => seeds = Some(numbers),
+                Ok(_) => issues.push(malformed("second \"seeds:\" line".to_string())),
+                Err(e) => issues.push(malformed(format!("invalid seed: {}", e))),
+            }
+        } else if let Some(header) = parse_header(trimmed) {
+            match header {
+                Ok((source, destination)) => {
+                    let map = CategoryMap { source, destination, line, ranges: vec![] };
+                    match first_defined.get(&map.name()) {
+                        Some(&first_line) => issues.push(AlmanacIssue::DuplicateMap { line, name: map.name(), first_line }),
+                        None => {
+                            first_defined.insert(map.name(), line);
+                        }
+                    }
+                    maps.push(map);
+                }
+                Err(reason) => issues.push(malformed(reason)),
+            }
+        } else {
+            match (parse_range_line(trimmed), maps.last_mut()) {
+                (Ok(range), Some(map)) => map.ranges.push((range, line)),
+                (Ok(_), None) => issues.push(AlmanacIssue::RangeOutsideMap { line }),
+                (Err(reason), _) => issues.push(malformed(reason)),
+            }
+        }
+    }
+
+    if seeds.is_none() {
+        issues.push(AlmanacIssue::MissingSeeds);
+    }
+    issues.extend(maps.iter().flat_map(|map| check_ranges(map, policy)));
+
+    if issues.iter().any(|issue| issue.severity() == Severity::Error) {
+        return Err(issues);
+    }
+    Ok(Almanac { seeds: seeds.unwrap_or_default(), maps, warnings: issues, overlap_policy: policy })
+}
+
+impl Almanac {
+    pub fn map(&self, source: &str, destination: &str) -> Option<&CategoryMap> {
+        self.maps.iter().find(|map| map.source == source && map.destination == destination)
+    }
+
+    // Every category named in a header, in the order they first appear
+    pub fn categories(&self) -> Vec<&str> {
+        let mut categories: Vec<&str> = vec![];
+        for map in &self.maps {
+            for category in [map.source.as_str(), map.destination.as_str()] {
+                if !categories.contains(&category) {
+                    categories.push(category);
+                }
+            }
+        }
+        categories
+    }
+
+    // The shortest chain of maps leading from one category to another
+    pub fn route(&self, from: &str, to: &str) -> Result<Vec<&CategoryMap>, String> {
+        let mut came_by: HashMap<&str, Option<&CategoryMap>> = HashMap::from([(from, None)]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                let mut route = vec![];
+                let mut current = to;
+                while let Some(Some(map)) = came_by.get(current) {
+                    route.push(*map);
+                    current = map.source.as_str();
+                }
+                route.reverse();
+                return Ok(route);
+            }
+            for map in self.maps.iter().filter(|map| map.source == category) {
+                if !came_by.contains_key(map.destination.as_str()) {
+                    came_by.insert(map.destination.as_str(), Some(map));
+                    queue.push_back(map.destination.as_str());
+                }
+            }
+        }
+
+        Err(format!("no chain of maps leads from {} to {}", from, to))
+    }
+
+    // A single mapper for the whole route between two categories
+    pub fn mapper_between(&self, from: &str, to: &str) -> Result<RangeMapper, String> {
+        self.route(from, to)?.iter().try_fold(RangeMapper::new(), |composed, map| {
+            Ok(composed.compose(&map.mapper(self.overlap_policy)?))
+        })
+    }
+
+    // The puzzle's seven stage seed-to-location chain
+    pub fn garden_mapper(&self) -> Result<GardenMapper, String> {
+        let stage = |source: &str, destination: &str| {
+            self.map(source, destination)
+                .ok_or_else(|| format!("missing {}-to-{} map", source, destination))?
+                .mapper(self.overlap_policy)
+        };
+        Ok(GardenMapper::new(
+            stage("seed", "soil")?,
+            stage("soil", "fertilizer")?,
+            stage("fertilizer", "water")?,
+            stage("water", "light")?,
+            stage("light", "temperature")?,
+            stage("temperature", "humidity")?,
+            stage("humidity", "location")?,
+        ))
+    }
+
+    // The value after each stage of the seed-to-location chain and the almanac line whose
+    // range mapped it. Looks the ranges up as listed, not in the merged mappers.
+    pub fn trace(&self, seed: i64) -> Result<SeedTrace, String> {
+        let mut value = seed;
+        let mut source = "seed";
+        let mut steps = vec![];
+        for category in STAGE_CATEGORIES {
+            let map = self.map(source, category).ok_or_else(|| format!("missing {}-to-{} map", source, category))?;
+            let range = map.matching_range(value, self.overlap_policy);
+            if let Some(listed) = &range {
+                value = listed.range.dest_start + (value - listed.range.src_start);
+            }
+            steps.push(TraceStep { category, value, range });
+            source = category;
+        }
+        Ok(SeedTrace { seed, steps })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+seeds: 79 14 55 13
+
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4
+";
+
+    #[test]
+    fn test_parse_example() {
+        let almanac = parse_almanac(EXAMPLE).unwrap();
+        assert_eq!(almanac.seeds, vec![79, 14, 55, 13]);
+        assert_eq!(almanac.maps.len(), 7);
+        assert_eq!(almanac.categories(), vec!["seed", "soil", "fertilizer", "water", "light", "temperature", "humidity", "location"]);
+        assert_eq!(almanac.maps[0].ranges[1], ((52, 50, 48), 5));
+
+        let garden_mapper = almanac.garden_mapper().unwrap();
+        assert_eq!(garden_mapper.find_smallest_location(&almanac.seeds), 35);
+    }
+
+    #[test]
+    fn test_trace_seed() {
+        let almanac = parse_almanac(EXAMPLE).unwrap();
+        let garden_mapper = almanac.garden_mapper().unwrap();
+
+        let trace = almanac.trace(79).unwrap();
+        let values: Vec<(&str, i64)> = trace.steps.iter().map(|step| (step.category, step.value)).collect();
+        assert_eq!(values, vec![("soil", 81), ("fertilizer", 81), ("water", 81), ("light", 74), ("temperature", 78), ("humidity", 78), ("location", 82)]);
+        assert_eq!(trace.location(), garden_mapper.find_location_for_seed(79));
+
+        assert_eq!(trace.steps[0].range, Some(ListedRange { range: SeedRange { dest_start: 52, src_start: 50, length: 48 }, line: 5 }));
+        // 81 is past the end of every soil-to-fertilizer range
+        assert_eq!(trace.steps[1].range, None);
+    }
+
+    #[test]
+    fn test_traced_ranges_are_almanac_lines() {
+        // Lines 3 and 4 carry on into each other and get merged, and line 5 overlaps line 3
+        let contents = "\
+seeds: 0
+seed-to-soil map:
+10 0 5
+15 5 5
+40 2 2
+soil-to-fertilizer map:
+0 10 5
+5 15 5
+fertilizer-to-water map:
+water-to-light map:
+light-to-temperature map:
+temperature-to-humidity map:
+humidity-to-location map:
+";
+        let lines: Vec<&str> = contents.lines().collect();
+        for policy in [OverlapPolicy::FirstWins, OverlapPolicy::LastWins, OverlapPolicy::Split] {
+            let almanac = parse_almanac_with_policy(contents, policy).unwrap();
+            let garden_mapper = almanac.garden_mapper().unwrap();
+            for seed in -1..12 {
+                let trace = almanac.trace(seed).unwrap();
+                assert_eq!(trace.location(), garden_mapper.find_location_for_seed(seed), "{} {}", policy, seed);
+                for listed in trace.steps.iter().filter_map(|step| step.range.as_ref()) {
+                    let SeedRange { dest_start, src_start, length } = listed.range;
+                    assert_eq!(lines[listed.line - 1], format!("{} {} {}", dest_start, src_start, length), "{} {}", policy, seed);
+                }
+            }
+        }
+
+        let almanac = parse_almanac(contents).unwrap();
+        let lines_used: Vec<Option<usize>> = almanac.trace(7).unwrap().steps.iter()
+            .map(|step| step.range.as_ref().map(|listed| listed.line))
+            .collect();
+        assert_eq!(lines_used, vec![Some(4), Some(8), None, None, None, None, None]);
+        let split = parse_almanac_with_policy(contents, OverlapPolicy::Split).unwrap();
+        assert_eq!(split.trace(3).unwrap().steps[0].range.as_ref().map(|listed| listed.line), Some(5));
+        assert!(parse_almanac("seeds: 1\nseed-to-soil map:\n").unwrap().trace(1).is_err());
+    }
+
+    #[test]
+    fn test_route_between_any_categories() {
+        let almanac = parse_almanac(EXAMPLE).unwrap();
+
+        let route: Vec<String> = almanac.route("soil", "light").unwrap().iter().map(|map| map.name()).collect();
+        assert_eq!(route, vec!["soil-to-fertilizer", "fertilizer-to-water", "water-to-light"]);
+        assert!(almanac.route("location", "seed").is_err());
+        assert!(almanac.route("seed", "seed").unwrap().is_empty());
+
+        let seed_to_location = almanac.mapper_between("seed", "location").unwrap();
+        assert_eq!(seed_to_location.map_number(79), 82);
+    }
+
+    #[test]
+    fn test_any_number_of_stages() {
+        let almanac = parse_almanac("\
+seeds: 1 5
+ore-to-metal map:
+10 0 3
+seed-to-ore map:
+0 5 1
+").unwrap();
+        assert_eq!(almanac.mapper_between("seed", "metal").unwrap().map_number(5), 10);
+        assert_eq!(almanac.garden_mapper().unwrap_err(), "missing seed-to-soil map");
+    }
+
+    #[test]
+    fn test_reports_errors_with_line_numbers() {
+        let issues = parse_almanac("\
+1 2 3
+seed-to-soil map:
+50 98 x
+52 50
+seed-soil map:
+seed-to-soil map:
+").unwrap_err();
+
+        assert_eq!(issues, vec![
+            AlmanacIssue::RangeOutsideMap { line: 1 },
+            AlmanacIssue::MalformedLine { line: 3, text: "50 98 x".to_string(), reason: "invalid number \"x\"".to_string() },
+            AlmanacIssue::MalformedLine { line: 4, text: "52 50".to_string(), reason: "expected 3 numbers, found 2".to_string() },
+            AlmanacIssue::MalformedLine { line: 5, text: "seed-soil map:".to_string(), reason: "expected a header like \"seed-to-soil map:\"".to_string() },
+            AlmanacIssue::DuplicateMap { line: 6, name: "seed-to-soil".to_string(), first_line: 2 },
+            AlmanacIssue::MissingSeeds,
+        ]);
+    }
+
+    #[test]
+    fn test_warns_about_overlaps_and_gaps() {
+        let almanac = parse_almanac("\
+seeds: 1
+seed-to-soil map:
+10 0 5
+15 3 4
+25 10 3
+").unwrap();
+
+        assert_eq!(almanac.warnings, vec![
+            AlmanacIssue::OverlappingRanges { map: "seed-to-soil".to_string(), line: 4, other_line: 3, policy: OverlapPolicy::FirstWins },
+            AlmanacIssue::Gap { map: "seed-to-soil".to_string(), start: 7, end: 10 },
+        ]);
+        assert_eq!(almanac.warnings[0].to_string(), "warning: line 4: seed-to-soil source range overlaps the range on line 3; the range listed first is used");
+        assert_eq!(almanac.warnings[1].to_string(), "warning: seed-to-soil: values 7..10 are not covered and map to themselves");
+    }
+
+    #[test]
+    fn test_overlap_policy_reaches_the_mappers() {
+        let contents = "\
+seeds: 4
+seed-to-soil map:
+10 0 5
+15 3 4
+";
+        let soil = |policy| {
+            let almanac = parse_almanac_with_policy(contents, policy).unwrap();
+            almanac.mapper_between("seed", "soil").unwrap().map_number(4)
+        };
+        assert_eq!(soil(OverlapPolicy::FirstWins), 14);
+        assert_eq!(soil(OverlapPolicy::LastWins), 16);
+        assert_eq!(soil(OverlapPolicy::Split), 16);
+
+        let issues = parse_almanac_with_policy(contents, OverlapPolicy::Reject).unwrap_err();
+        assert_eq!(issues, vec![
+            AlmanacIssue::OverlappingRanges { map: "seed-to-soil".to_string(), line: 4, other_line: 3, policy: OverlapPolicy::Reject },
+        ]);
+        assert_eq!(issues[0].to_string(), "error: line 4: seed-to-soil source range overlaps the range on line 3");
+    }
+}
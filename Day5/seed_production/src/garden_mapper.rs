@@ -0,0 +1,799 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+use rayon::prelude::*;
+use serde::Serialize;
+use crate::almanac::{parse_almanac, parse_range_line};
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct SeedRange {
+    pub dest_start: i64,
+    pub src_start: i64,
+    pub length: i64,
+}
+impl SeedRange {
+    pub fn contains(&self, value: i64) -> bool {
+        value >= self.src_start && value < self.src_start + self.length
+    }
+}
+
+// Which range maps a source value that more than one range in a map covers
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OverlapPolicy {
+    // Overlapping ranges are an error
+    Reject,
+    // The range listed first
+    FirstWins,
+    // The range listed last
+    LastWins,
+    // The shortest range, as the most specific one, so a range listed inside a longer one
+    // splits it around itself. Equal lengths go to the first listed.
+    Split,
+}
+
+impl OverlapPolicy {
+    // Orders the ranges covering a value so that the one this policy picks comes first.
+    // `index` is where the range was listed in its map.
+    pub fn rank(&self, index: usize, range: &SeedRange) -> (i64, i64) {
+        match self {
+            OverlapPolicy::Reject | OverlapPolicy::FirstWins => (0, index as i64),
+            OverlapPolicy::LastWins => (0, -(index as i64)),
+            OverlapPolicy::Split => (range.length, index as i64),
+        }
+    }
+}
+
+impl FromStr for OverlapPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(OverlapPolicy::Reject),
+            "first-wins" => Ok(OverlapPolicy::FirstWins),
+            "last-wins" => Ok(OverlapPolicy::LastWins),
+            "split" => Ok(OverlapPolicy::Split),
+            _ => Err(format!("unknown overlap policy: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for OverlapPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OverlapPolicy::Reject => write!(f, "reject"),
+            OverlapPolicy::FirstWins => write!(f, "first-wins"),
+            OverlapPolicy::LastWins => write!(f, "last-wins"),
+            OverlapPolicy::Split => write!(f, "split"),
+        }
+    }
+}
+
+// "dest_start src_start length", as the range was written in the almanac
+fn describe(range: &SeedRange) -> String {
+    format!("\"{} {} {}\"", range.dest_start, range.src_start, range.length)
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RangeMapper {
+    range_map: Vec<SeedRange>,
+}
+
+impl Default for RangeMapper {
+    fn default() -> Self {
+        RangeMapper::new()
+    }
+}
+
+impl RangeMapper {
+    // Initializes a new RangeMapper with an empty map
+    pub fn new() -> Self {
+        RangeMapper { range_map: Vec::new() }
+    }
+
+    // Loads a range mapping into the RangeMapper and merges the ranges. Where ranges overlap
+    // the one listed first wins.
+    pub fn load_map(&mut self, map_data: Vec<(i64, i64, i64)>) {
+        self.load_map_with_policy(map_data, OverlapPolicy::FirstWins)
+            .expect("only OverlapPolicy::Reject fails");
+    }
+
+    // Loads a range mapping, resolving overlapping ranges with `policy`
+    pub fn load_map_with_policy(&mut self, map_data: Vec<(i64, i64, i64)>, policy: OverlapPolicy) -> Result<(), String> {
+        let initial_ranges: Vec<SeedRange> = map_data.into_iter()
+            .map(|(dest_start, src_start, length)| SeedRange { dest_start, src_start, length })
+            .filter(|range| range.length > 0)
+            .collect();
+
+        self.range_map = self.merge_ranges(initial_ranges, policy)?;
+        Ok(())
+    }
+
+    // Cuts the ranges (in the order they were listed) at every source boundary, gives each
+    // piece to the range `policy` picks, and joins pieces that carry on in both source and
+    // destination. The result is sorted by source and has no overlaps.
+    fn merge_ranges(&self, ranges: Vec<SeedRange>, policy: OverlapPolicy) -> Result<Vec<SeedRange>, String> {
+        let mut boundaries: Vec<i64> = ranges.iter()
+            .flat_map(|range| [range.src_start, range.src_start + range.length])
+            .collect();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        let mut by_start: Vec<usize> = (0..ranges.len()).collect();
+        by_start.sort_by_key(|&index| ranges[index].src_start);
+        let mut by_end = by_start.clone();
+        by_end.sort_by_key(|&index| ranges[index].src_start + ranges[index].length);
+        let (mut starts, mut ends) = (by_start.into_iter().peekable(), by_end.into_iter().peekable());
+
+        // The ranges covering the current piece, keyed by rank so the winner comes first
+        let mut covering: BTreeMap<(i64, i64), usize> = BTreeMap::new();
+        let mut merged: Vec<SeedRange> = vec![];
+        for piece in boundaries.windows(2) {
+            let (start, end) = (piece[0], piece[1]);
+            while let Some(index) = starts.next_if(|&index| ranges[index].src_start <= start) {
+                covering.insert(policy.rank(index, &ranges[index]), index);
+            }
+            while let Some(index) = ends.next_if(|&index| ranges[index].src_start + ranges[index].length <= start) {
+                covering.remove(&policy.rank(index, &ranges[index]));
+            }
+
+            let mut candidates = covering.values().map(|&index| &ranges[index]);
+            let winner = match (candidates.next(), candidates.next(), policy) {
+                (None, _, _) => continue,
+                (Some(first), Some(second), OverlapPolicy::Reject) => return Err(format!(
+                    "ranges {} and {} both map {}..{}", describe(first), describe(second), start, end)),
+                (Some(winner), _, _) => winner,
+            };
+
+            let dest_start = winner.dest_start + (start - winner.src_start);
+            match merged.last_mut() {
+                Some(last) if last.src_start + last.length == start && last.dest_start + last.length == dest_start => {
+                    last.length += end - start;
+                }
+                _ => merged.push(SeedRange { dest_start, src_start: start, length: end - start }),
+            }
+        }
+        Ok(merged)
+    }
+
+    // Maps a number based on the loaded range map
+    pub fn map_number(&self, source: i64) -> i64 {
+        match self.matching_range(source) {
+            Some(range) => range.dest_start + (source - range.src_start),
+            None => source, // Default to source if not in any range
+        }
+    }
+
+    // The SeedRange that maps `source`, if any
+    pub fn matching_range(&self, source: i64) -> Option<&SeedRange> {
+        self.range_map.iter().find(|range| range.contains(source))
+    }
+
+    // Splits `range` wherever it crosses a SeedRange boundary and pairs each piece with the
+    // offset it is shifted by (0 for the gaps between SeedRanges). Pieces are in source order.
+    // An empty or inverted range has no pieces.
+    pub fn pieces(&self, range: Range<i64>) -> Vec<(Range<i64>, i64)> {
+        let mut pieces = vec![];
+        if range.is_empty() {
+            return pieces;
+        }
+        let mut cursor = range.start;
+
+        for seed_range in &self.range_map {
+            let src_end = seed_range.src_start + seed_range.length;
+            if src_end <= cursor {
+                continue;
+            }
+            if seed_range.src_start >= range.end {
+                break;
+            }
+            // The gap before this SeedRange maps to itself
+            if cursor < seed_range.src_start {
+                pieces.push((cursor..seed_range.src_start, 0));
+                cursor = seed_range.src_start;
+            }
+            let piece_end = src_end.min(range.end);
+            pieces.push((cursor..piece_end, seed_range.dest_start - seed_range.src_start));
+            cursor = piece_end;
+        }
+
+        if cursor < range.end {
+            pieces.push((cursor..range.end, 0));
+        }
+        pieces
+    }
+
+    // Maps every value in `range`, splitting it wherever it crosses a SeedRange boundary.
+    // The pieces come back in source order, so they are not sorted by destination.
+    pub fn map_range(&self, range: Range<i64>) -> Vec<Range<i64>> {
+        self.pieces(range).into_iter()
+            .map(|(piece, offset)| piece.start + offset..piece.end + offset)
+            .collect()
+    }
+
+    // Maps a set of ranges and returns the result sorted, with overlapping and touching ranges joined
+    pub fn map_ranges(&self, ranges: &[Range<i64>]) -> Vec<Range<i64>> {
+        normalize_ranges(ranges.iter().flat_map(|range| self.map_range(range.clone())).collect())
+    }
+
+    // The smallest range outside of which the mapper is the identity
+    fn span(&self) -> Option<Range<i64>> {
+        let start = self.range_map.iter().map(|range| range.src_start).min()?;
+        let end = self.range_map.iter().map(|range| range.src_start + range.length).max()?;
+        Some(start..end)
+    }
+
+    // A single mapper equivalent to mapping with `self` and then with `next`
+    pub fn compose(&self, next: &RangeMapper) -> RangeMapper {
+        // Outside both spans both mappers are the identity, and so is the composition
+        let span = match (self.span(), next.span()) {
+            (Some(a), Some(b)) => a.start.min(b.start)..a.end.max(b.end),
+            (Some(span), None) | (None, Some(span)) => span,
+            (None, None) => return RangeMapper::new(),
+        };
+
+        let mut range_map: Vec<SeedRange> = vec![];
+        for (piece, first_offset) in self.pieces(span) {
+            let shifted = piece.start + first_offset..piece.end + first_offset;
+            for (middle, second_offset) in next.pieces(shifted) {
+                let offset = first_offset + second_offset;
+                let src_start = middle.start - first_offset;
+                let length = middle.end - middle.start;
+                match range_map.last_mut() {
+                    // Join pieces that carry on with the same shift
+                    Some(last) if last.src_start + last.length == src_start
+                        && last.dest_start - last.src_start == offset => last.length += length,
+                    _ if offset == 0 => {}
+                    _ => range_map.push(SeedRange { dest_start: src_start + offset, src_start, length }),
+                }
+            }
+        }
+        RangeMapper { range_map }
+    }
+
+    // Every source value that maps to `value`
+    pub fn preimage(&self, value: i64) -> Vec<i64> {
+        let mut sources: Vec<i64> = self.range_map.iter()
+            .filter(|range| value >= range.dest_start && value < range.dest_start + range.length)
+            .map(|range| range.src_start + (value - range.dest_start))
+            .collect();
+        if !self.range_map.iter().any(|range| range.contains(value)) {
+            sources.push(value);
+        }
+        sources.sort();
+        sources
+    }
+
+    // The inverse mapper, if every value has exactly one source. That holds when the
+    // SeedRanges' destinations don't overlap and cover exactly the same values as their sources.
+    pub fn invert(&self) -> Option<RangeMapper> {
+        let sources: Vec<Range<i64>> = self.range_map.iter().map(|range| range.src_start..range.src_start + range.length).collect();
+        let destinations: Vec<Range<i64>> = self.range_map.iter().map(|range| range.dest_start..range.dest_start + range.length).collect();
+
+        let covered: i64 = normalize_ranges(destinations.clone()).iter().map(|range| range.end - range.start).sum();
+        let total: i64 = destinations.iter().map(|range| range.end - range.start).sum();
+        if covered != total || normalize_ranges(sources) != normalize_ranges(destinations) {
+            return None;
+        }
+
+        Some(RangeMapper::from_vec(self.range_map.iter()
+            .map(|range| (range.src_start, range.dest_start, range.length))
+            .collect()))
+    }
+
+    // Source values where the shift changes, in ascending order
+    pub fn breakpoints(&self) -> Vec<i64> {
+        let mut breakpoints: Vec<i64> = vec![];
+        let span = match self.span() {
+            Some(span) => span,
+            None => return breakpoints,
+        };
+        let mut previous_offset = 0;
+        for (piece, offset) in self.pieces(span.clone()) {
+            if offset != previous_offset {
+                breakpoints.push(piece.start);
+            }
+            previous_offset = offset;
+        }
+        if previous_offset != 0 {
+            breakpoints.push(span.end);
+        }
+        breakpoints
+    }
+
+    pub fn from_vec(map_data: Vec<(i64, i64, i64)>) -> Self {
+        // use load_map
+        let mut mapper = RangeMapper::new();
+        mapper.load_map(map_data);
+        mapper
+    }
+
+    pub fn from_vec_with_policy(map_data: Vec<(i64, i64, i64)>, policy: OverlapPolicy) -> Result<Self, String> {
+        let mut mapper = RangeMapper::new();
+        mapper.load_map_with_policy(map_data, policy)?;
+        Ok(mapper)
+    }
+    // Reads "dest_start src_start length" lines, skipping blank lines and "X-to-Y map:" headers
+    pub fn from_string(data: &str) -> Result<Self, String> {
+        let range_map = data.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.ends_with("map:"))
+            .map(|line| parse_range_line(line).map_err(|e| format!("{}: \"{}\"", e, line)))
+            .collect::<Result<Vec<(i64, i64, i64)>, String>>()?;
+        Ok(RangeMapper::from_vec(range_map))
+    }
+}
+
+// The category each stage of the seed-to-location chain maps into
+pub const STAGE_CATEGORIES: [&str; 7] = ["soil", "fertilizer", "water", "light", "temperature", "humidity", "location"];
+
+// A range as a map lists it, before any merging, and the 1-based line it was on
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct ListedRange {
+    #[serde(flatten)]
+    pub range: SeedRange,
+    pub line: usize,
+}
+
+// One stage of a seed's trace: the value it was mapped to and the listed range that did it,
+// or None where the value mapped to itself
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct TraceStep {
+    pub category: &'static str,
+    pub value: i64,
+    pub range: Option<ListedRange>,
+}
+
+// Every value a seed takes on its way to a location
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct SeedTrace {
+    pub seed: i64,
+    pub steps: Vec<TraceStep>,
+}
+
+impl SeedTrace {
+    pub fn location(&self) -> i64 {
+        self.steps.last().map_or(self.seed, |step| step.value)
+    }
+}
+
+#[derive(Debug)]
+pub struct GardenMapper {
+    seed_to_soil: RangeMapper,
+    soil_to_fertilizer: RangeMapper,
+    fertilizer_to_water: RangeMapper,
+    water_to_light: RangeMapper,
+    light_to_temperature: RangeMapper,
+    temperature_to_humidity: RangeMapper,
+    humidity_to_location: RangeMapper,
+}
+
+impl GardenMapper {
+    pub fn new(
+        seed_to_soil: RangeMapper,
+        soil_to_fertilizer: RangeMapper,
+        fertilizer_to_water: RangeMapper,
+        water_to_light: RangeMapper,
+        light_to_temperature: RangeMapper,
+        temperature_to_humidity: RangeMapper,
+        humidity_to_location: RangeMapper,
+    ) -> Self {
+        GardenMapper {
+            seed_to_soil,
+            soil_to_fertilizer,
+            fertilizer_to_water,
+            water_to_light,
+            light_to_temperature,
+            temperature_to_humidity,
+            humidity_to_location,
+        }
+    }
+
+    pub fn find_smallest_location(&self, seeds: &[i64]) -> i64 {
+        seeds.iter().map(|&seed| {
+            self.find_location_for_seed(seed)
+        }).min().unwrap_or(i64::MAX) // Returns the smallest location or i64::MAX if no seeds are provided
+    }
+
+    // The seven mappers from seed to location, in the order they are applied
+    pub fn stages(&self) -> [&RangeMapper; 7] {
+        [
+            &self.seed_to_soil,
+            &self.soil_to_fertilizer,
+            &self.fertilizer_to_water,
+            &self.water_to_light,
+            &self.light_to_temperature,
+            &self.temperature_to_humidity,
+            &self.humidity_to_location,
+        ]
+    }
+
+    // The whole chain as one mapper from seed straight to location
+    pub fn compose(&self) -> RangeMapper {
+        self.stages().iter().fold(RangeMapper::new(), |composed, stage| composed.compose(stage))
+    }
+
+    // Every seed that ends up at `location`
+    pub fn seeds_for_location(&self, location: i64) -> Vec<i64> {
+        self.compose().preimage(location)
+    }
+
+    // Every location reachable from the seed ranges, sorted and joined
+    pub fn map_seed_ranges_to_locations(&self, seed_ranges: &[Range<i64>]) -> Vec<Range<i64>> {
+        self.stages().iter().fold(normalize_ranges(seed_ranges.to_vec()), |ranges, mapper| mapper.map_ranges(&ranges))
+    }
+
+    // Same answer as `process_seed_ranges` for (start, length) seed ranges, without visiting each seed
+    pub fn find_smallest_location_in_ranges(&self, seed_ranges: &[(i64, i64)]) -> i64 {
+        let ranges: Vec<Range<i64>> = seed_ranges.iter().map(|&(start, length)| start..start + length).collect();
+        self.map_seed_ranges_to_locations(&ranges)
+            .first()
+            .map_or(i64::MAX, |range| range.start)
+    }
+
+    pub fn find_location_for_seed(&self, seed: i64) -> i64 {
+        let soil = self.seed_to_soil.map_number(seed);
+        let fertilizer = self.soil_to_fertilizer.map_number(soil);
+        let water = self.fertilizer_to_water.map_number(fertilizer);
+        let light = self.water_to_light.map_number(water);
+        let temperature = self.light_to_temperature.map_number(light);
+        let humidity = self.temperature_to_humidity.map_number(temperature);
+        self.humidity_to_location.map_number(humidity)
+    }
+}
+// Sorts ranges and joins any that overlap or touch, dropping empty ones
+pub fn normalize_ranges(mut ranges: Vec<Range<i64>>) -> Vec<Range<i64>> {
+    ranges.retain(|range| range.start < range.end);
+    ranges.sort_by_key(|range| range.start);
+
+    let mut normalized: Vec<Range<i64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match normalized.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => normalized.push(range),
+        }
+    }
+    normalized
+}
+
+// Parses the puzzle input into its seeds and the seed-to-location chain, panicking on a bad almanac
+pub fn parse_file_contents(file_contents: &str) -> (Vec<i64>, GardenMapper) {
+    let almanac = parse_almanac(file_contents).unwrap_or_else(|issues| {
+        let issues: Vec<String> = issues.iter().map(|issue| issue.to_string()).collect();
+        panic!("invalid almanac:\n{}", issues.join("\n"))
+    });
+    let garden_mapper = almanac.garden_mapper().unwrap_or_else(|e| panic!("invalid almanac: {}", e));
+    (almanac.seeds, garden_mapper)
+}
+
+pub fn expand_seed_ranges(ranges: &[(i64, i64)]) -> Vec<i64> {
+    let mut seeds = Vec::new();
+    for (start, length) in ranges {
+        seeds.extend(*start..*start + *length);
+    }
+    seeds
+}
+
+pub fn process_seed_ranges(seed_ranges: Vec<(i64, i64)>, garden_mapper: &GardenMapper) -> i64 {
+    seed_ranges.into_par_iter()
+        .flat_map(|(start, length)| start..start + length)
+        .map(|seed| {
+            // Process each seed to find its corresponding location
+            garden_mapper.find_location_for_seed(seed)
+        })
+        .min()
+        .unwrap_or(i64::MAX)  // Or handle the case when there are no seeds differently
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_to_soil_mapping() {
+        let mut mapper = RangeMapper::new();
+        mapper.load_map(vec![(50, 98, 2), (52, 50, 48)]);
+
+        assert_eq!(mapper.map_number(79), 81);
+        assert_eq!(mapper.map_number(14), 14);
+        assert_eq!(mapper.map_number(55), 57);
+        assert_eq!(mapper.map_number(13), 13);
+    }
+
+    #[test]
+    fn test_mapping_through_categories() {
+
+        // Seeds to be tested
+        let seeds : Vec<i64> = vec![79, 14, 55, 13];
+        let garden_mapper = prepare_test_garden();
+
+
+
+        // Find the smallest location using the GardenMapper
+        let smallest_location = garden_mapper.find_smallest_location(&seeds);
+
+        // Assert that the smallest location is as expected
+        assert_eq!(smallest_location, 35);
+    }
+
+    fn prepare_test_garden() -> GardenMapper {
+// Instantiate each RangeMapper using the from_vec constructor
+        let seed_to_soil_mapper = RangeMapper::from_vec(vec![(50, 98, 2), (52, 50, 48)]);
+        let soil_to_fertilizer_mapper = RangeMapper::from_vec(vec![(0, 15, 37), (37, 52, 2), (39, 0, 15)]);
+        let fertilizer_to_water_mapper = RangeMapper::from_vec(vec![(49, 53, 8), (0, 11, 42), (42, 0, 7), (57, 7, 4)]);
+        let water_to_light_mapper = RangeMapper::from_vec(vec![(88, 18, 7), (18, 25, 70)]);
+        let light_to_temperature_mapper = RangeMapper::from_vec(vec![(45, 77, 23), (81, 45, 19), (68, 64, 13)]);
+        let temperature_to_humidity_mapper = RangeMapper::from_vec(vec![(0, 69, 1), (1, 0, 69)]);
+        let humidity_to_location_mapper = RangeMapper::from_vec(vec![(60, 56, 37), (56, 93, 4)]);
+
+        // Create a GardenMapper instance
+        GardenMapper::new(
+            seed_to_soil_mapper,
+            soil_to_fertilizer_mapper,
+            fertilizer_to_water_mapper,
+            water_to_light_mapper,
+            light_to_temperature_mapper,
+            temperature_to_humidity_mapper,
+            humidity_to_location_mapper,
+        )
+    }
+
+    #[test]
+    fn test_with_expanded_seeds(){
+        // seeds = seeds: 79 14 55 13
+
+        let expanded_seeds = expand_seed_ranges(&[(79,14), (55,13)]);
+        let garden_mapper = prepare_test_garden();
+
+
+
+        // Find the smallest location using the GardenMapper
+        let smallest_location = garden_mapper.find_smallest_location(&expanded_seeds);
+
+        // Assert that the smallest location is as expected
+        assert_eq!(smallest_location, 46);
+    }
+
+    #[test]
+    fn test_seed_to_soil_mapping2() {
+        let mut mapper = RangeMapper::new();
+        mapper.load_map(vec![(50, 98, 2), (52, 50, 48)]);
+
+        assert_eq!(mapper.map_number(79), 81);
+        assert_eq!(mapper.map_number(14), 14);
+        assert_eq!(mapper.map_number(55), 57);
+        assert_eq!(mapper.map_number(13), 13);
+    }
+
+
+    // dest: 10-14, src: 0-4 overlaps dest: 15-18, src: 3-6 at src 3-4
+    fn overlapping_map() -> Vec<(i64, i64, i64)> {
+        vec![
+            (10, 0, 5),  // dest: 10-14, src: 0-4
+            (15, 3, 4),  // dest: 15-18, src: 3-6
+            (20, 7, 2),  // dest: 20-21, src: 7-8
+            (25, 10, 3), // dest: 25-27, src: 10-12
+        ]
+    }
+
+    #[test]
+    fn test_load_map_with_overlapping_ranges() {
+        let mut mapper = RangeMapper::new();
+        mapper.load_map(overlapping_map());
+
+        // The first range keeps 3-4, and the second keeps only 5-6 but still maps them to 17-18
+        let expected_ranges = vec![
+            SeedRange { dest_start: 10, src_start: 0, length: 5 },
+            SeedRange { dest_start: 17, src_start: 5, length: 2 },
+            SeedRange { dest_start: 20, src_start: 7, length: 2 },
+            SeedRange { dest_start: 25, src_start: 10, length: 3 },
+        ];
+
+        assert_eq!(mapper.range_map, expected_ranges);
+        assert_eq!(mapper.map_number(4), 14);
+        assert_eq!(mapper.map_number(6), 18);
+    }
+
+    #[test]
+    fn test_load_map_overlap_policies() {
+        let load = |policy| RangeMapper::from_vec_with_policy(overlapping_map(), policy).map(|mapper| mapper.range_map);
+
+        assert_eq!(load(OverlapPolicy::Reject), Err("ranges \"10 0 5\" and \"15 3 4\" both map 3..5".to_string()));
+        assert_eq!(load(OverlapPolicy::FirstWins).unwrap()[..2], [
+            SeedRange { dest_start: 10, src_start: 0, length: 5 },
+            SeedRange { dest_start: 17, src_start: 5, length: 2 },
+        ]);
+        assert_eq!(load(OverlapPolicy::LastWins).unwrap()[..2], [
+            SeedRange { dest_start: 10, src_start: 0, length: 3 },
+            SeedRange { dest_start: 15, src_start: 3, length: 4 },
+        ]);
+        // The shorter second range wins the overlap, whichever order the two are listed in
+        assert_eq!(load(OverlapPolicy::Split).unwrap(), load(OverlapPolicy::LastWins).unwrap());
+        let mut reversed = overlapping_map();
+        reversed.swap(0, 1);
+        assert_eq!(RangeMapper::from_vec_with_policy(reversed, OverlapPolicy::Split).unwrap().range_map, load(OverlapPolicy::Split).unwrap());
+        // Equal lengths go to the first listed
+        let tied = RangeMapper::from_vec_with_policy(vec![(100, 0, 4), (50, 2, 4)], OverlapPolicy::Split).unwrap();
+        assert_eq!(tied.map_number(3), 103);
+
+        // A range inside another splits it in two
+        let nested = RangeMapper::from_vec_with_policy(vec![(100, 0, 10), (50, 4, 2)], OverlapPolicy::Split).unwrap();
+        assert_eq!(nested.range_map, vec![
+            SeedRange { dest_start: 100, src_start: 0, length: 4 },
+            SeedRange { dest_start: 50, src_start: 4, length: 2 },
+            SeedRange { dest_start: 106, src_start: 6, length: 4 },
+        ]);
+
+        let disjoint = vec![(50, 98, 2), (52, 50, 48)];
+        for policy in [OverlapPolicy::Reject, OverlapPolicy::FirstWins, OverlapPolicy::LastWins, OverlapPolicy::Split] {
+            assert_eq!(RangeMapper::from_vec_with_policy(disjoint.clone(), policy), Ok(RangeMapper::from_vec(disjoint.clone())));
+        }
+    }
+
+    #[test]
+    fn test_merged_ranges_map_like_the_ranked_listed_range() {
+        let maps = vec![
+            overlapping_map(),
+            vec![(100, 0, 10), (50, 4, 2), (70, 3, 8), (0, 9, 1)],
+            vec![(5, 0, 3), (9, 0, 3), (1, 1, 1)],
+        ];
+        for map in maps {
+            let listed: Vec<SeedRange> = map.iter()
+                .map(|&(dest_start, src_start, length)| SeedRange { dest_start, src_start, length })
+                .collect();
+            for policy in [OverlapPolicy::FirstWins, OverlapPolicy::LastWins, OverlapPolicy::Split] {
+                let mapper = RangeMapper::from_vec_with_policy(map.clone(), policy).unwrap();
+                for value in -2..20 {
+                    let expected = listed.iter().enumerate()
+                        .filter(|(_, range)| range.contains(value))
+                        .min_by_key(|(index, range)| policy.rank(*index, range))
+                        .map_or(value, |(_, range)| range.dest_start + value - range.src_start);
+                    assert_eq!(mapper.map_number(value), expected, "{:?} {} {}", map, policy, value);
+                }
+            }
+        }
+        assert_eq!("last-wins".parse::<OverlapPolicy>(), Ok(OverlapPolicy::LastWins));
+        assert!("middle-wins".parse::<OverlapPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_load_map_joins_contiguous_ranges() {
+        let mut mapper = RangeMapper::new();
+        // src 0-4 -> 10-14 carries straight on into src 5-9 -> 15-19, but not into src 10-14 -> 30-34
+        mapper.load_map(vec![(15, 5, 5), (10, 0, 5), (30, 10, 5)]);
+
+        assert_eq!(mapper.range_map, vec![
+            SeedRange { dest_start: 10, src_start: 0, length: 10 },
+            SeedRange { dest_start: 30, src_start: 10, length: 5 },
+        ]);
+    }
+
+    #[test]
+    fn test_load_map_sorting() {
+        let mut mapper = RangeMapper::new();
+        // Providing unsorted range data
+        let map_data = vec![
+            (30, 10, 5),  // dest: 30-34, src: 10-14
+            (10, 0, 5),   // dest: 10-14, src: 0-4
+            (20, 5, 5),   // dest: 20-24, src: 5-9
+        ];
+
+        mapper.load_map(map_data);
+
+        // Expected ranges after sorting (but before merging)
+        let expected_sorted_ranges = vec![
+            SeedRange { dest_start: 10, src_start: 0, length: 5 },
+            SeedRange { dest_start: 20, src_start: 5, length: 5 },
+            SeedRange { dest_start: 30, src_start: 10, length: 5 },
+        ];
+
+        // Check if the ranges are sorted correctly
+        assert_eq!(mapper.range_map, expected_sorted_ranges);
+    }
+
+    #[test]
+    fn test_map_range_splits_at_boundaries() {
+        let mapper = RangeMapper::from_vec(vec![(50, 98, 2), (52, 50, 48)]);
+
+        assert_eq!(mapper.map_range(45..100), vec![45..50, 52..100, 50..52]);
+        assert_eq!(mapper.map_range(60..70), vec![62..72]);
+        assert_eq!(mapper.map_range(0..10), vec![0..10]);
+        assert_eq!(mapper.map_range(99..120), vec![51..52, 100..120]);
+        assert!(mapper.map_range(60..60).is_empty());
+        assert!(mapper.pieces(Range { start: 70, end: 60 }).is_empty());
+        assert_eq!(mapper.map_ranges(&[45..100, 0..10]), vec![0..10, 45..100]);
+    }
+
+    #[test]
+    fn test_map_range_matches_map_number() {
+        let garden_mapper = prepare_test_garden();
+
+        for mapper in garden_mapper.stages() {
+            let mut by_range: Vec<i64> = mapper.map_range(0..120).into_iter().flatten().collect();
+            let mut by_number: Vec<i64> = (0..120).map(|value| mapper.map_number(value)).collect();
+            by_range.sort();
+            by_number.sort();
+            assert_eq!(by_range, by_number);
+        }
+    }
+
+    #[test]
+    fn test_interval_locations_match_brute_force() {
+        let garden_mapper = prepare_test_garden();
+        let seed_ranges = vec![(79, 14), (55, 13)];
+
+        let mut brute_force: Vec<i64> = expand_seed_ranges(&seed_ranges).iter()
+            .map(|&seed| garden_mapper.find_location_for_seed(seed))
+            .collect();
+        brute_force.sort();
+        brute_force.dedup();
+
+        let ranges: Vec<Range<i64>> = seed_ranges.iter().map(|&(start, length)| start..start + length).collect();
+        let by_interval: Vec<i64> = garden_mapper.map_seed_ranges_to_locations(&ranges).into_iter().flatten().collect();
+
+        assert_eq!(by_interval, brute_force);
+        assert_eq!(garden_mapper.find_smallest_location_in_ranges(&seed_ranges), 46);
+        assert_eq!(process_seed_ranges(seed_ranges, &garden_mapper), 46);
+    }
+
+    #[test]
+    fn test_compose_two_mappers() {
+        let first = RangeMapper::from_vec(vec![(50, 98, 2), (52, 50, 48)]);
+        let second = RangeMapper::from_vec(vec![(0, 15, 37), (37, 52, 2), (39, 0, 15)]);
+        let composed = first.compose(&second);
+
+        for value in 0..120 {
+            assert_eq!(composed.map_number(value), second.map_number(first.map_number(value)), "value {}", value);
+        }
+        assert_eq!(composed.breakpoints(), vec![0, 15, 50, 52, 98, 100]);
+    }
+
+    #[test]
+    fn test_composed_chain_matches_stage_by_stage() {
+        let garden_mapper = prepare_test_garden();
+        let composed = garden_mapper.compose();
+
+        for seed in 0..120 {
+            assert_eq!(composed.map_number(seed), garden_mapper.find_location_for_seed(seed), "seed {}", seed);
+        }
+        assert_eq!(composed.map_number(79), 82);
+        assert_eq!(garden_mapper.seeds_for_location(82), vec![79]);
+        assert_eq!(garden_mapper.seeds_for_location(46), vec![82]);
+    }
+
+    #[test]
+    fn test_invert() {
+        let mapper = RangeMapper::from_vec(vec![(50, 98, 2), (52, 50, 48)]);
+        let inverse = mapper.invert().unwrap();
+        for value in 0..120 {
+            assert_eq!(inverse.map_number(mapper.map_number(value)), value);
+        }
+        assert_eq!(mapper.preimage(51), vec![99]);
+
+        // 5 and 10 both map to 10, and nothing maps to 5
+        let not_one_to_one = RangeMapper::from_vec(vec![(10, 5, 1)]);
+        assert!(not_one_to_one.invert().is_none());
+        assert_eq!(not_one_to_one.preimage(10), vec![5, 10]);
+        assert!(not_one_to_one.preimage(5).is_empty());
+    }
+
+    #[test]
+    fn test_from_string_rejects_bad_numbers() {
+        let mapper = RangeMapper::from_string("seed-to-soil map:\n50 98 2\n\n52 50 48\n").unwrap();
+        assert_eq!(mapper, RangeMapper::from_vec(vec![(50, 98, 2), (52, 50, 48)]));
+        assert_eq!(RangeMapper::from_string("50 98 two").unwrap_err(), "invalid number \"two\": \"50 98 two\"");
+    }
+
+    #[test]
+    fn test_normalize_ranges() {
+        assert_eq!(normalize_ranges(vec![5..8, 0..2, 2..3, 7..10, 4..4]), vec![0..3, 5..10]);
+    }
+
+    #[test]
+    fn test_expand_seed_ranges(){
+        let seed_tuples:Vec<(i64,i64)> = vec![(79,14), (55,13) ];
+        let expanded_seeds = expand_seed_ranges(&seed_tuples);
+        assert_eq!(expanded_seeds.len(), 27);
+        assert_eq!(expanded_seeds[0], 79);
+        assert_eq!(expanded_seeds[26], 67);
+    }
+}
\ No newline at end of file
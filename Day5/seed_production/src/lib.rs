@@ -0,0 +1,3 @@
+pub mod garden_mapper;
+pub mod almanac;
+pub mod solve;
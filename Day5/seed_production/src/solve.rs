@@ -0,0 +1,165 @@
+use std::fmt;
+use std::str::FromStr;
+use rayon::prelude::*;
+use crate::garden_mapper::GardenMapper;
+
+// How the numbers on the "seeds:" line are read
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SeedInterpretation {
+    // Every number is a seed (part 1)
+    Individual,
+    // Numbers come in (start, length) pairs (part 2)
+    Ranges,
+}
+
+impl SeedInterpretation {
+    // The seeds as (start, length) ranges; an individual seed is a range of length 1
+    pub fn seed_ranges(&self, numbers: &[i64]) -> Result<Vec<(i64, i64)>, String> {
+        match self {
+            SeedInterpretation::Individual => Ok(numbers.iter().map(|&seed| (seed, 1)).collect()),
+            SeedInterpretation::Ranges if !numbers.len().is_multiple_of(2) => {
+                Err(format!("seed ranges need pairs of numbers, got {} numbers", numbers.len()))
+            }
+            SeedInterpretation::Ranges => Ok(numbers.chunks(2).map(|pair| (pair[0], pair[1])).collect()),
+        }
+    }
+}
+
+impl FromStr for SeedInterpretation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "individual" => Ok(SeedInterpretation::Individual),
+            "ranges" => Ok(SeedInterpretation::Ranges),
+            _ => Err(format!("unknown seed interpretation: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for SeedInterpretation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SeedInterpretation::Individual => write!(f, "individual"),
+            SeedInterpretation::Ranges => write!(f, "ranges"),
+        }
+    }
+}
+
+// How the smallest location is found
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    // Maps every seed in turn on one thread
+    BruteForce,
+    // Maps every seed, spread over all cores
+    Rayon,
+    // Maps whole ranges at once, splitting them at range boundaries
+    Interval,
+}
+
+impl Algorithm {
+    pub fn smallest_location(&self, garden_mapper: &GardenMapper, seed_ranges: &[(i64, i64)]) -> i64 {
+        let seeds = |&(start, length): &(i64, i64)| start..start + length;
+        match self {
+            Algorithm::BruteForce => seed_ranges.iter()
+                .flat_map(seeds)
+                .map(|seed| garden_mapper.find_location_for_seed(seed))
+                .min()
+                .unwrap_or(i64::MAX),
+            Algorithm::Rayon => seed_ranges.par_iter()
+                .flat_map(seeds)
+                .map(|seed| garden_mapper.find_location_for_seed(seed))
+                .min()
+                .unwrap_or(i64::MAX),
+            Algorithm::Interval => garden_mapper.find_smallest_location_in_ranges(seed_ranges),
+        }
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "brute-force" => Ok(Algorithm::BruteForce),
+            "rayon" => Ok(Algorithm::Rayon),
+            "interval" => Ok(Algorithm::Interval),
+            _ => Err(format!("unknown algorithm: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Algorithm::BruteForce => write!(f, "brute-force"),
+            Algorithm::Rayon => write!(f, "rayon"),
+            Algorithm::Interval => write!(f, "interval"),
+        }
+    }
+}
+
+// How many seeds the ranges hold, without listing them
+pub fn seed_count(seed_ranges: &[(i64, i64)]) -> i64 {
+    seed_ranges.iter().map(|&(_, length)| length.max(0)).sum()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::almanac::parse_almanac;
+
+    const EXAMPLE: &str = "\
+seeds: 79 14 55 13
+seed-to-soil map:
+50 98 2
+52 50 48
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+water-to-light map:
+88 18 7
+18 25 70
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+temperature-to-humidity map:
+0 69 1
+1 0 69
+humidity-to-location map:
+60 56 37
+56 93 4
+";
+
+    #[test]
+    fn test_seed_interpretations() {
+        let numbers = [79, 14, 55, 13];
+        assert_eq!(SeedInterpretation::Individual.seed_ranges(&numbers), Ok(vec![(79, 1), (14, 1), (55, 1), (13, 1)]));
+        assert_eq!(SeedInterpretation::Ranges.seed_ranges(&numbers), Ok(vec![(79, 14), (55, 13)]));
+        assert!(SeedInterpretation::Ranges.seed_ranges(&[1, 2, 3]).is_err());
+        assert_eq!(seed_count(&[(79, 14), (55, 13)]), 27);
+    }
+
+    #[test]
+    fn test_algorithms_agree() {
+        let almanac = parse_almanac(EXAMPLE).unwrap();
+        let garden_mapper = almanac.garden_mapper().unwrap();
+
+        for algorithm in ["brute-force", "rayon", "interval"] {
+            let algorithm: Algorithm = algorithm.parse().unwrap();
+            for (interpretation, expected) in [(SeedInterpretation::Individual, 35), (SeedInterpretation::Ranges, 46)] {
+                let seed_ranges = interpretation.seed_ranges(&almanac.seeds).unwrap();
+                assert_eq!(algorithm.smallest_location(&garden_mapper, &seed_ranges), expected, "{} {}", algorithm, interpretation);
+            }
+        }
+        assert!("quantum".parse::<Algorithm>().is_err());
+    }
+}
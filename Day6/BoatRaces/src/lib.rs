@@ -0,0 +1,3 @@
+pub mod race;
+pub mod race_sheet;
+pub mod strategy;
@@ -1,146 +1,95 @@
-use std::fs;
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
-use regex::Regex;
-
-fn calculate_ways_to_win(times: &[i32], records: &[i32]) -> i32 {
-    let mut total_ways = 1;
-
-    for (&time, &record) in times.iter().zip(records.iter()) {
-        let mut ways_to_win = 0;
+use clap::{App, Arg};
+use std::process;
+use boat_races::race::product_of_ways_to_win;
+use boat_races::race_sheet::{parse_races, Kerning, Race, SheetFormat};
+use boat_races::strategy::{AccelerationModel, StrategyReport};
+
+fn main() {
+    let matches = App::new("Boat Races")
+        .about("Counts the ways to beat each race's record distance")
+        .arg(Arg::with_name("file")
+            .short('f')
+            .long("file")
+            .value_name("FILE")
+            .help("Sets the race sheet, CSV or JSON race list to read")
+            .takes_value(true)
+            .default_value("input_file.txt"))
+        .arg(Arg::with_name("format")
+            .long("format")
+            .value_name("FORMAT")
+            .help("Reads the file as a race sheet, CSV or JSON; guessed from the extension by default")
+            .takes_value(true)
+            .possible_values(["sheet", "csv", "json"]))
+        .arg(Arg::with_name("mode")
+            .long("mode")
+            .value_name("MODE")
+            .help("Reads each column as a race, or runs each line's numbers together into one race")
+            .takes_value(true)
+            .possible_values(["spaced", "kerned", "both"])
+            .default_value("both"))
+        .arg(Arg::with_name("model")
+            .long("model")
+            .value_name("MODEL")
+            .help("linear, quadratic, capped:TOP_SPEED or battery:DRAIN; reports the winning window and best hold for each race, repeat to compare")
+            .takes_value(true)
+            .multiple_occurrences(true))
+        .get_matches();
+
+    let filename = matches.value_of("file").unwrap();
+    let format = match matches.value_of("format") {
+        Some(format) => format.parse().unwrap(),
+        None => SheetFormat::from_path(filename),
+    };
+    let kernings = match matches.value_of("mode").unwrap() {
+        "both" => vec![Kerning::Spaced, Kerning::Kerned],
+        mode => vec![mode.parse().unwrap()],
+    };
+    let models: Vec<AccelerationModel> = matches.values_of("model").into_iter().flatten()
+        .map(|model| model.parse().unwrap_or_else(|e: String| {
+            eprintln!("{}", e);
+            process::exit(1);
+        }))
+        .collect();
 
-        for hold_time in 0..time {
-            let distance = hold_time * (time - hold_time);
-            if distance > record {
-                ways_to_win += 1;
-            }
+    let contents = std::fs::read_to_string(filename).unwrap_or_else(|e| {
+        eprintln!("could not read {}: {}", filename, e);
+        process::exit(1);
+    });
+
+    for kerning in kernings {
+        let races = parse_races(&contents, format, kerning).unwrap_or_else(|e| {
+            eprintln!("{}: {}", filename, e);
+            process::exit(1);
+        });
+        if !models.is_empty() {
+            print_reports(&races, &models);
+            continue;
         }
-
-        total_ways *= ways_to_win;
-    }
-
-    total_ways
-}
-
-fn calculate_ways_to_win_single_race(time: i64, record: i64) -> i64 {
-    let mut ways_to_win = 0;
-
-    for hold_time in 0..time {
-        let distance = hold_time * (time - hold_time);
-        if distance > record {
-            ways_to_win += 1;
+        let result = product_of_ways_to_win(&races).unwrap_or_else(|e| {
+            eprintln!("{}: {}", filename, e);
+            process::exit(1);
+        });
+        match kerning {
+            Kerning::Spaced => println!("Number of ways to win multiple races: {}", result),
+            Kerning::Kerned => println!("Number of ways to win a single race: {}", result),
         }
     }
-
-    ways_to_win
-}
-
-
-
-fn read_input_file<P: AsRef<Path>>(filename: P) -> io::Result<(Vec<i32>, Vec<i32>)> {
-    let file = File::open(filename)?;
-    let lines: Vec<String> = io::BufReader::new(file).lines().filter_map(|line| line.ok()).collect();
-
-    if lines.len() != 2 {
-        return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid number of lines"));
-    }
-
-    let times = lines[0].split_whitespace()
-        .skip(1) // Skip the "Time:" label
-        .filter_map(|s| s.parse().ok())
-        .collect();
-
-    let distances = lines[1].split_whitespace()
-        .skip(1) // Skip the "Distance:" label
-        .filter_map(|s| s.parse().ok())
-        .collect();
-
-    Ok((times, distances))
 }
 
-
-fn read_input_file_single_race<P: AsRef<Path>>(filename: P) -> io::Result<(i64, i64)> {
-
-        let file = File::open(filename)?;
-        let lines: Vec<String> = io::BufReader::new(file).lines().filter_map(|line| line.ok()).collect();
-
-        if lines.len() != 2 {
-            return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid number of lines"));
+// One row per race and model
+fn print_reports(races: &[Race], models: &[AccelerationModel]) {
+    println!("{:>16} {:>20} {:>14} {:>33} {:>12} {:>12} {:>22} {:>22}",
+        "Time", "Record", "Model", "Winning holds", "Ways", "Best hold", "Best distance", "Margin");
+    for &race in races {
+        for model in models {
+            let report: StrategyReport = model.report(race);
+            let window = match &report.window {
+                Some(window) => format!("{}..={}", window.start(), window.end()),
+                None => "none".to_string(),
+            };
+            println!("{:>16} {:>20} {:>14} {:>33} {:>12} {:>12} {:>22} {:>22}",
+                race.time, race.distance, model.to_string(), window, report.ways_to_win(),
+                report.optimal_hold, report.best_distance, report.margin());
         }
-
-        let time_s_vec:Vec<&str> = lines[0].split_whitespace()
-            .skip(1) // Skip the "Time:" label
-            .collect();
-        let time = time_s_vec.join("").parse::<i64>().unwrap();
-        let distance_s_vec:Vec<&str> = lines[1].split_whitespace()
-            .skip(1) // Skip the "Time:" label
-            .collect();
-        let distance = distance_s_vec.join("").parse::<i64>().unwrap();
-
-        Ok((time, distance))
-}
-
-
-fn main() -> io::Result<()> {
-    let (times, distances) = read_input_file("input_file.txt")?;
-
-    let result = calculate_ways_to_win(&times, &distances);
-    println!("Number of ways to win multiple races: {}", result);
-
-    let (time, distance) = read_input_file_single_race("input_file.txt")?;
-    let result2 = calculate_ways_to_win_single_race(time, distance);
-    println!("Number of ways to win a single race: {}", result2);
-    Ok(())
-}
-
-
-
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_calculate_ways_to_win() {
-        let times = vec![7, 15, 30];
-        let records = vec![9, 40, 200];
-        let expected = 288; // The expected result based on the example
-        assert_eq!(calculate_ways_to_win(&times, &records), expected);
-    }
-
-    #[test]
-    fn test_calculate_ways_to_win_single_race() {
-        let time = 71530;
-        let distance = 940200;
-        let expected_ways_to_win = 71503; // Expected result based on the example
-
-        assert_eq!(
-            calculate_ways_to_win_single_race(time, distance),
-            expected_ways_to_win
-        );
     }
-
-    #[test]
-    fn test_calculate_ways_to_win_single_race2() {
-        let time = 40929790;
-        let distance = 215106415051100;
-        let expected_ways_to_win = 28545089; // Expected result based on the example
-
-        assert_eq!(
-            calculate_ways_to_win_single_race(time, distance),
-            expected_ways_to_win
-        );
-    }
-
-    #[test]
-    fn test_read_input_single_race(){
-        let expected_time = 40929790;
-        let expected_distance = 215106415051100;
-        let (time, distance) = read_input_file_single_race("input_file.txt").unwrap();
-        assert_eq!(time, expected_time);
-        assert_eq!(distance, expected_distance);
-    }
-
 }
-
@@ -0,0 +1,229 @@
+use crate::race_sheet::Race;
+
+// Holding the button for `hold_time` ms leaves `time - hold_time` ms to travel at `hold_time` mm/ms
+
+// Tries every hold time of every race. Kept as the reference that `product_of_ways_to_win`
+// is checked against.
+pub fn calculate_ways_to_win(times: &[i32], records: &[i32]) -> i32 {
+    let mut total_ways = 1;
+
+    for (&time, &record) in times.iter().zip(records.iter()) {
+        let mut ways_to_win = 0;
+        for hold_time in 0..time {
+            let distance = hold_time * (time - hold_time);
+            if distance > record {
+                ways_to_win += 1;
+            }
+        }
+        total_ways *= ways_to_win;
+    }
+
+    total_ways
+}
+
+// Tries every hold time. Kept as the reference that `ways_to_win` is checked against.
+pub fn calculate_ways_to_win_single_race(time: i64, distance: i64) -> i64 {
+    let mut ways_to_win = 0;
+
+    for hold_time in 0..time {
+        let distance_travelled = hold_time * (time - hold_time);
+        if distance_travelled > distance {
+            ways_to_win += 1;
+        }
+    }
+
+    ways_to_win
+}
+
+// hold_time * (time - hold_time) > record, without multiplying. For a > 0, a * b > d
+// exactly when b > d / a rounded down.
+fn beats_record(time: u128, hold_time: u128, record: u128) -> bool {
+    hold_time > 0 && hold_time <= time && time - hold_time > record / hold_time
+}
+
+// The shortest winning hold time, if any hold time wins. The distance is symmetric about
+// time / 2 and grows up to it, so the winning hold times are first..=time - first.
+fn shortest_winning_hold(time: u128, record: u128) -> Option<u128> {
+    let half = time / 2;
+    if !beats_record(time, half, record) {
+        return None;
+    }
+
+    // The roots of h^2 - time * h + record = 0 are (time -+ sqrt(time^2 - 4 * record)) / 2
+    let discriminant = time.checked_mul(time).and_then(|square| square.checked_sub(record.checked_mul(4)?));
+    let first = match discriminant {
+        Some(discriminant) => {
+            // The integer square root can put the estimate one either side of the boundary
+            let mut first = (time - discriminant.isqrt()) / 2;
+            while first > 0 && beats_record(time, first - 1, record) {
+                first -= 1;
+            }
+            while !beats_record(time, first, record) {
+                first += 1;
+            }
+            first
+        }
+        // time^2 doesn't fit in a u128, so search for the boundary instead
+        None => shortest_winning_hold_by_search(time, record, half),
+    };
+    Some(first)
+}
+
+// Binary search of 0..=half, where `half` is known to win
+fn shortest_winning_hold_by_search(time: u128, record: u128, half: u128) -> u128 {
+    let (mut low, mut high) = (0, half);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if beats_record(time, middle, record) {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+    low
+}
+
+// How many hold times beat the record, without trying each one
+pub fn ways_to_win(time: u128, record: u128) -> u128 {
+    match shortest_winning_hold(time, record) {
+        Some(first) => time - 2 * first + 1,
+        None => 0,
+    }
+}
+
+pub fn ways_to_win_u64(time: u64, record: u64) -> u64 {
+    // At most time - 1 hold times win, so this always fits
+    ways_to_win(time as u128, record as u128) as u64
+}
+
+// The number of ways to win each race multiplied together, or an error naming the 1-based
+// race that takes the product past u128::MAX
+pub fn product_of_ways_to_win(races: &[Race]) -> Result<u128, String> {
+    races.iter().enumerate().try_fold(1u128, |product, (index, race)| {
+        product.checked_mul(ways_to_win(race.time as u128, race.distance as u128))
+            .ok_or_else(|| format!("the number of ways to win overflows at race {}", index + 1))
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_calculate_ways_to_win() {
+        let times = vec![7, 15, 30];
+        let records = vec![9, 40, 200];
+        let expected = 288; // The expected result based on the example
+
+        assert_eq!(calculate_ways_to_win(&times, &records), expected);
+    }
+
+    #[test]
+    fn test_product_of_ways_to_win() {
+        let races = [Race { time: 7, distance: 9 }, Race { time: 15, distance: 40 }, Race { time: 30, distance: 200 }];
+        assert_eq!(product_of_ways_to_win(&races), Ok(288));
+        assert_eq!(product_of_ways_to_win(&[]), Ok(1));
+
+        let times: Vec<i32> = (0..60).step_by(7).collect();
+        let records: Vec<i32> = times.iter().map(|time| time * time / 5).collect();
+        let races: Vec<Race> = times.iter().zip(&records)
+            .map(|(&time, &distance)| Race { time: time as u64, distance: distance as u64 })
+            .collect();
+        assert_eq!(product_of_ways_to_win(&races[1..]), Ok(calculate_ways_to_win(&times[1..], &records[1..]) as u128));
+        assert_eq!(product_of_ways_to_win(&races), Ok(0));
+    }
+
+    #[test]
+    fn test_product_of_ways_to_win_overflow() {
+        let race = Race { time: u64::MAX, distance: 0 };
+        assert_eq!(product_of_ways_to_win(&[race, race]), Ok((u64::MAX as u128 - 1) * (u64::MAX as u128 - 1)));
+        assert_eq!(
+            product_of_ways_to_win(&[race, race, race]),
+            Err("the number of ways to win overflows at race 3".to_string())
+        );
+    }
+
+    #[test]
+    fn test_calculate_ways_to_win_single_race() {
+        let time = 71530;
+        let distance = 940200;
+        let expected_ways_to_win = 71503; // Expected result based on the example
+
+        assert_eq!(
+            calculate_ways_to_win_single_race(time, distance),
+            expected_ways_to_win
+        );
+    }
+
+    #[test]
+    fn test_calculate_ways_to_win_single_race2() {
+        let time = 40929790;
+        let distance = 215106415051100;
+        let expected_ways_to_win = 28545089; // Expected result based on the example
+
+        assert_eq!(
+            calculate_ways_to_win_single_race(time, distance),
+            expected_ways_to_win
+        );
+    }
+
+    #[test]
+    fn test_ways_to_win_examples() {
+        assert_eq!(ways_to_win_u64(7, 9), 4);
+        assert_eq!(ways_to_win_u64(15, 40), 8);
+        // Holding for 10 or 20 only ties the record
+        assert_eq!(ways_to_win_u64(30, 200), 9);
+        assert_eq!(ways_to_win_u64(71530, 940200), 71503);
+        assert_eq!(ways_to_win_u64(40929790, 215106415051100), 28545089);
+    }
+
+    #[test]
+    fn test_ways_to_win_edges() {
+        assert_eq!(ways_to_win_u64(0, 0), 0);
+        assert_eq!(ways_to_win_u64(1, 0), 0);
+        assert_eq!(ways_to_win_u64(2, 0), 1);
+        // The best hold time exactly ties the record
+        assert_eq!(ways_to_win_u64(4, 4), 0);
+        assert_eq!(ways_to_win_u64(4, 3), 1);
+        assert_eq!(ways_to_win_u64(u64::MAX, 0), u64::MAX - 1);
+        assert_eq!(ways_to_win(u128::MAX, 0), u128::MAX - 1);
+        assert_eq!(ways_to_win(u128::MAX, u128::MAX), u128::MAX - 3);
+    }
+
+    proptest! {
+        #[test]
+        fn closed_form_matches_loop(time in 0i64..3000, record_fraction in 0.0f64..1.1) {
+            let record = ((time * time) as f64 / 4.0 * record_fraction) as i64;
+            prop_assert_eq!(
+                ways_to_win_u64(time as u64, record as u64) as i64,
+                calculate_ways_to_win_single_race(time, record)
+            );
+        }
+
+        #[test]
+        fn closed_form_matches_search(time in 0u128..1 << 64, record_fraction in 0.0f64..1.1) {
+            let record = (time as f64 * time as f64 / 4.0 * record_fraction) as u128;
+            let expected = if beats_record(time, time / 2, record) {
+                time - 2 * shortest_winning_hold_by_search(time, record, time / 2) + 1
+            } else {
+                0
+            };
+            prop_assert_eq!(ways_to_win(time, record), expected);
+        }
+
+        #[test]
+        fn window_is_tight(time in 1u128..u128::MAX, record in any::<u128>()) {
+            if let Some(first) = shortest_winning_hold(time, record) {
+                prop_assert!(beats_record(time, first, record));
+                prop_assert!(beats_record(time, time - first, record));
+                prop_assert!(first == 0 || !beats_record(time, first - 1, record));
+                prop_assert!(!beats_record(time, time - first + 1, record));
+            } else {
+                prop_assert!(!beats_record(time, time / 2, record));
+                prop_assert!(!beats_record(time, time - time / 2, record));
+            }
+        }
+    }
+}
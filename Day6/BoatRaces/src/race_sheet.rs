@@ -0,0 +1,265 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+
+// One race: how long it lasts and the distance to beat, in ms and mm
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Race {
+    pub time: u64,
+    pub distance: u64,
+}
+
+// How the numbers on a race sheet are read
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kerning {
+    // Each column is a race (part 1)
+    Spaced,
+    // The spaces are bad kerning and each line is one number (part 2)
+    Kerned,
+}
+
+impl FromStr for Kerning {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "spaced" => Ok(Kerning::Spaced),
+            "kerned" => Ok(Kerning::Kerned),
+            _ => Err(format!("unknown kerning: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Kerning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Kerning::Spaced => write!(f, "spaced"),
+            Kerning::Kerned => write!(f, "kerned"),
+        }
+    }
+}
+
+// The layouts a list of races can be read from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SheetFormat {
+    // The puzzle's "Time:" and "Distance:" lines
+    Sheet,
+    // A "time,distance" header and one race per row
+    Csv,
+    // An array of {"time": .., "distance": ..} objects
+    Json,
+}
+
+impl SheetFormat {
+    // Guesses from the file extension, defaulting to a race sheet
+    pub fn from_path(path: &str) -> SheetFormat {
+        match path.rsplit_once('.').map(|(_, extension)| extension.to_ascii_lowercase()) {
+            Some(extension) if extension == "csv" => SheetFormat::Csv,
+            Some(extension) if extension == "json" => SheetFormat::Json,
+            _ => SheetFormat::Sheet,
+        }
+    }
+}
+
+impl FromStr for SheetFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sheet" => Ok(SheetFormat::Sheet),
+            "csv" => Ok(SheetFormat::Csv),
+            "json" => Ok(SheetFormat::Json),
+            _ => Err(format!("unknown race list format: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RaceSheetError {
+    // Lines are 1-based
+    MissingLine(&'static str),
+    BadLabel { line: usize, expected: &'static str, found: String },
+    // `column` is the 1-based number on the line, after the label
+    BadNumber { line: usize, column: usize, value: String },
+    ColumnCountMismatch { times: usize, distances: usize },
+    UnexpectedLine(usize),
+    NoRaces,
+    // Numbers that run together into one too large for a u64
+    KernedTooLarge(String),
+    Csv(String),
+    Json(String),
+}
+
+impl fmt::Display for RaceSheetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RaceSheetError::MissingLine(label) => write!(f, "missing \"{}\" line", label),
+            RaceSheetError::BadLabel { line, expected, found } => {
+                write!(f, "line {}: expected \"{}\", found \"{}\"", line, expected, found)
+            }
+            RaceSheetError::BadNumber { line, column, value } => {
+                write!(f, "line {}: invalid number \"{}\" in column {}", line, value, column)
+            }
+            RaceSheetError::ColumnCountMismatch { times, distances } => {
+                write!(f, "{} times but {} distances", times, distances)
+            }
+            RaceSheetError::UnexpectedLine(line) => write!(f, "line {}: unexpected line after the distances", line),
+            RaceSheetError::NoRaces => write!(f, "no races"),
+            RaceSheetError::KernedTooLarge(digits) => write!(f, "kerned number {} is too large", digits),
+            RaceSheetError::Csv(e) => write!(f, "invalid CSV: {}", e),
+            RaceSheetError::Json(e) => write!(f, "invalid JSON: {}", e),
+        }
+    }
+}
+
+// The numbers after `label` on one line, checked to be digits but kept as written
+fn labelled_numbers<'a>(line: usize, text: &'a str, label: &'static str) -> Result<Vec<&'a str>, RaceSheetError> {
+    let rest = text.trim_start().strip_prefix(label).ok_or_else(|| RaceSheetError::BadLabel {
+        line,
+        expected: label,
+        found: text.split_whitespace().next().unwrap_or("").to_string(),
+    })?;
+
+    let numbers: Vec<&str> = rest.split_whitespace().collect();
+    for (index, value) in numbers.iter().enumerate() {
+        if value.parse::<u64>().is_err() {
+            return Err(RaceSheetError::BadNumber { line, column: index + 1, value: value.to_string() });
+        }
+    }
+    Ok(numbers)
+}
+
+// Runs the digits together into one number
+fn kern(numbers: &[String]) -> Result<u64, RaceSheetError> {
+    let digits = numbers.concat();
+    digits.parse().map_err(|_| RaceSheetError::KernedTooLarge(digits))
+}
+
+// Pairs times with distances, or joins each list into one race when kerned
+fn races_from_columns(times: &[String], distances: &[String], kerning: Kerning) -> Result<Vec<Race>, RaceSheetError> {
+    if times.len() != distances.len() {
+        return Err(RaceSheetError::ColumnCountMismatch { times: times.len(), distances: distances.len() });
+    }
+    if times.is_empty() {
+        return Err(RaceSheetError::NoRaces);
+    }
+    match kerning {
+        // Every number was checked when it was read
+        Kerning::Spaced => Ok(times.iter().zip(distances)
+            .map(|(time, distance)| Race { time: time.parse().unwrap(), distance: distance.parse().unwrap() })
+            .collect()),
+        Kerning::Kerned => Ok(vec![Race { time: kern(times)?, distance: kern(distances)? }]),
+    }
+}
+
+// Reads a "Time:" line then a "Distance:" line. Blank lines are ignored.
+pub fn parse_race_sheet(contents: &str, kerning: Kerning) -> Result<Vec<Race>, RaceSheetError> {
+    let mut lines = contents.lines().enumerate()
+        .map(|(index, text)| (index + 1, text))
+        .filter(|(_, text)| !text.trim().is_empty());
+
+    let (line, text) = lines.next().ok_or(RaceSheetError::MissingLine("Time:"))?;
+    let times = labelled_numbers(line, text, "Time:")?;
+    let (line, text) = lines.next().ok_or(RaceSheetError::MissingLine("Distance:"))?;
+    let distances = labelled_numbers(line, text, "Distance:")?;
+    if let Some((line, _)) = lines.next() {
+        return Err(RaceSheetError::UnexpectedLine(line));
+    }
+
+    let owned = |numbers: Vec<&str>| numbers.into_iter().map(str::to_string).collect::<Vec<String>>();
+    races_from_columns(&owned(times), &owned(distances), kerning)
+}
+
+fn from_races(races: Vec<Race>, kerning: Kerning) -> Result<Vec<Race>, RaceSheetError> {
+    let times: Vec<String> = races.iter().map(|race| race.time.to_string()).collect();
+    let distances: Vec<String> = races.iter().map(|race| race.distance.to_string()).collect();
+    races_from_columns(&times, &distances, kerning)
+}
+
+pub fn parse_race_csv(contents: &str, kerning: Kerning) -> Result<Vec<Race>, RaceSheetError> {
+    let races = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(contents.as_bytes())
+        .deserialize()
+        .collect::<Result<Vec<Race>, csv::Error>>()
+        .map_err(|e| RaceSheetError::Csv(e.to_string()))?;
+    from_races(races, kerning)
+}
+
+pub fn parse_race_json(contents: &str, kerning: Kerning) -> Result<Vec<Race>, RaceSheetError> {
+    let races: Vec<Race> = serde_json::from_str(contents).map_err(|e| RaceSheetError::Json(e.to_string()))?;
+    from_races(races, kerning)
+}
+
+pub fn parse_races(contents: &str, format: SheetFormat, kerning: Kerning) -> Result<Vec<Race>, RaceSheetError> {
+    match format {
+        SheetFormat::Sheet => parse_race_sheet(contents, kerning),
+        SheetFormat::Csv => parse_race_csv(contents, kerning),
+        SheetFormat::Json => parse_race_json(contents, kerning),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Time:      7  15   30\nDistance:  9  40  200\n";
+
+    fn race(time: u64, distance: u64) -> Race {
+        Race { time, distance }
+    }
+
+    #[test]
+    fn test_parse_spaced_and_kerned() {
+        assert_eq!(parse_race_sheet(EXAMPLE, Kerning::Spaced), Ok(vec![race(7, 9), race(15, 40), race(30, 200)]));
+        assert_eq!(parse_race_sheet(EXAMPLE, Kerning::Kerned), Ok(vec![race(71530, 940200)]));
+    }
+
+    #[test]
+    fn test_read_input_single_race() {
+        let contents = std::fs::read_to_string("input_file.txt").unwrap();
+        assert_eq!(parse_race_sheet(&contents, Kerning::Kerned), Ok(vec![race(40929790, 215106415051100)]));
+    }
+
+    #[test]
+    fn test_race_sheet_errors() {
+        assert_eq!(
+            parse_race_sheet("Time: 7 15\nDistance: 9 40 200\n", Kerning::Spaced),
+            Err(RaceSheetError::ColumnCountMismatch { times: 2, distances: 3 })
+        );
+        assert_eq!(
+            parse_race_sheet("Time: 7\nRecord: 9\n", Kerning::Spaced),
+            Err(RaceSheetError::BadLabel { line: 2, expected: "Distance:", found: "Record:".to_string() })
+        );
+        assert_eq!(
+            parse_race_sheet("\nTime: 7 1x\nDistance: 9 40\n", Kerning::Kerned),
+            Err(RaceSheetError::BadNumber { line: 2, column: 2, value: "1x".to_string() })
+        );
+        assert_eq!(parse_race_sheet("Time: 7\n", Kerning::Spaced), Err(RaceSheetError::MissingLine("Distance:")));
+        assert_eq!(parse_race_sheet("Time: 7\nDistance: 9\nTime: 8\n", Kerning::Spaced), Err(RaceSheetError::UnexpectedLine(3)));
+        assert_eq!(parse_race_sheet("Time:\nDistance:\n", Kerning::Spaced), Err(RaceSheetError::NoRaces));
+        assert!(matches!(
+            parse_race_sheet("Time: 9999999999 9999999999\nDistance: 1 1\n", Kerning::Kerned),
+            Err(RaceSheetError::KernedTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_csv_and_json() {
+        let csv = "time, distance\n7, 9\n15, 40\n30, 200\n";
+        let json = r#"[{"time": 7, "distance": 9}, {"time": 15, "distance": 40}, {"time": 30, "distance": 200}]"#;
+
+        assert_eq!(parse_races(csv, SheetFormat::Csv, Kerning::Spaced), parse_race_sheet(EXAMPLE, Kerning::Spaced));
+        assert_eq!(parse_races(json, SheetFormat::Json, Kerning::Kerned), Ok(vec![race(71530, 940200)]));
+        assert!(matches!(parse_race_csv("time,distance\n7,x\n", Kerning::Spaced), Err(RaceSheetError::Csv(_))));
+        assert!(matches!(parse_race_json(r#"[{"time": 7}]"#, Kerning::Spaced), Err(RaceSheetError::Json(_))));
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(SheetFormat::from_path("races.CSV"), SheetFormat::Csv);
+        assert_eq!(SheetFormat::from_path("races.json"), SheetFormat::Json);
+        assert_eq!(SheetFormat::from_path("input_file.txt"), SheetFormat::Sheet);
+    }
+}
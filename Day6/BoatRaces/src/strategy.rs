@@ -0,0 +1,229 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use crate::race::ways_to_win;
+use crate::race_sheet::Race;
+
+// How fast the boat goes after the button is held for a number of ms
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccelerationModel {
+    // 1 mm/ms for every ms held (the puzzle's boats)
+    Linear,
+    // The square of the ms held
+    Quadratic,
+    // 1 mm/ms for every ms held, up to a top speed
+    Capped(u64),
+    // 1 mm/ms for every ms held, then slowing by the drain every ms the boat moves
+    BatteryDrain(u64),
+}
+
+impl AccelerationModel {
+    // How far the boat goes in a race of `time` ms, saturating at u128::MAX
+    pub fn distance(&self, time: u64, hold_time: u64) -> u128 {
+        if hold_time >= time {
+            return 0;
+        }
+        let (hold, moving) = (hold_time as u128, (time - hold_time) as u128);
+        match *self {
+            AccelerationModel::Linear => hold * moving,
+            AccelerationModel::Quadratic => hold.saturating_mul(hold).saturating_mul(moving),
+            AccelerationModel::Capped(top_speed) => hold.min(top_speed as u128) * moving,
+            AccelerationModel::BatteryDrain(0) => hold * moving,
+            AccelerationModel::BatteryDrain(drain) => {
+                // Moves for `steps` ms at hold, hold - drain, ... until it stops or time runs out
+                let drain = drain as u128;
+                let steps = moving.min(hold.div_ceil(drain));
+                steps * hold - drain * (steps * steps.saturating_sub(1) / 2)
+            }
+        }
+    }
+
+    // The hold times that beat the record, the shortest hold with the longest distance,
+    // and that distance
+    pub fn solve(&self, race: Race) -> (Option<RangeInclusive<u64>>, u64, u128) {
+        match self {
+            AccelerationModel::Linear => {
+                let optimal_hold = race.time / 2;
+                let ways = ways_to_win(race.time as u128, race.distance as u128);
+                // The winning hold times sit evenly about time / 2
+                let first = ((race.time as u128 + 1 - ways) / 2) as u64;
+                let window = if ways == 0 { None } else { Some(first..=first + (ways - 1) as u64) };
+                (window, optimal_hold, self.distance(race.time, optimal_hold))
+            }
+            _ => self.solve_numerically(race),
+        }
+    }
+
+    // Every model's distance rises to a peak and then falls, so the peak and both edges of
+    // the winning window can be found by binary search
+    fn solve_numerically(&self, race: Race) -> (Option<RangeInclusive<u64>>, u64, u128) {
+        let distance = |hold_time: u64| self.distance(race.time, hold_time);
+        let record = race.distance as u128;
+
+        // The first hold time that does no better than the next one
+        let optimal_hold = first_where(0, race.time, |hold| distance(hold + 1) <= distance(hold));
+        let best_distance = distance(optimal_hold);
+        if best_distance <= record {
+            return (None, optimal_hold, best_distance);
+        }
+
+        let first = first_where(0, optimal_hold, |hold| distance(hold) > record);
+        let after_last = first_where(optimal_hold, race.time, |hold| distance(hold) <= record);
+        (Some(first..=after_last - 1), optimal_hold, best_distance)
+    }
+
+    pub fn report(&self, race: Race) -> StrategyReport {
+        let (window, optimal_hold, best_distance) = self.solve(race);
+        StrategyReport { model: *self, race, window, optimal_hold, best_distance }
+    }
+}
+
+// The first value in low..=high where `predicate` holds, given it holds from some point on.
+// Returns high when it only holds there, or nowhere.
+fn first_where(mut low: u64, mut high: u64, predicate: impl Fn(u64) -> bool) -> u64 {
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if predicate(middle) {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+    low
+}
+
+// Parses "linear", "quadratic", "capped:TOP_SPEED" or "battery:DRAIN"
+impl FromStr for AccelerationModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |value: &str, what: &str| value.trim().parse::<u64>().map_err(|_| format!("invalid {}: {}", what, value));
+        match s {
+            "linear" => Ok(AccelerationModel::Linear),
+            "quadratic" => Ok(AccelerationModel::Quadratic),
+            _ => match s.split_once(':') {
+                Some(("capped", top_speed)) => number(top_speed, "top speed").map(AccelerationModel::Capped),
+                Some(("battery", drain)) => number(drain, "drain").map(AccelerationModel::BatteryDrain),
+                _ => Err(format!("unknown acceleration model: {}", s)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for AccelerationModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AccelerationModel::Linear => write!(f, "linear"),
+            AccelerationModel::Quadratic => write!(f, "quadratic"),
+            AccelerationModel::Capped(top_speed) => write!(f, "capped:{}", top_speed),
+            AccelerationModel::BatteryDrain(drain) => write!(f, "battery:{}", drain),
+        }
+    }
+}
+
+// How one model does in one race
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrategyReport {
+    pub model: AccelerationModel,
+    pub race: Race,
+    pub window: Option<RangeInclusive<u64>>,
+    pub optimal_hold: u64,
+    pub best_distance: u128,
+}
+
+impl StrategyReport {
+    pub fn ways_to_win(&self) -> u64 {
+        self.window.as_ref().map_or(0, |window| window.end() - window.start() + 1)
+    }
+
+    // How far the best hold beats the record by; negative when the record can't be beaten
+    pub fn margin(&self) -> i128 {
+        let best = i128::try_from(self.best_distance).unwrap_or(i128::MAX);
+        best - self.race.distance as i128
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn race(time: u64, distance: u64) -> Race {
+        Race { time, distance }
+    }
+
+    // Tries every hold time
+    fn solve_by_trying(model: AccelerationModel, race: Race) -> (Option<RangeInclusive<u64>>, u64, u128) {
+        let distances: Vec<u128> = (0..=race.time).map(|hold| model.distance(race.time, hold)).collect();
+        let best_distance = *distances.iter().max().unwrap();
+        let optimal_hold = distances.iter().position(|&d| d == best_distance).unwrap() as u64;
+        let winning: Vec<u64> = (0..=race.time).filter(|&hold| distances[hold as usize] > race.distance as u128).collect();
+        let window = winning.first().map(|&first| first..=*winning.last().unwrap());
+        if let Some(window) = &window {
+            assert_eq!(winning.len() as u64, window.end() - window.start() + 1, "{} {:?} wins in pieces", model, race);
+        }
+        (window, optimal_hold, best_distance)
+    }
+
+    #[test]
+    fn test_linear_matches_the_puzzle() {
+        let report = AccelerationModel::Linear.report(race(30, 200));
+        assert_eq!(report.window, Some(11..=19));
+        assert_eq!(report.ways_to_win(), 9);
+        assert_eq!(report.optimal_hold, 15);
+        assert_eq!(report.best_distance, 225);
+        assert_eq!(report.margin(), 25);
+
+        let report = AccelerationModel::Linear.report(race(40929790, 215106415051100));
+        assert_eq!(report.ways_to_win(), 28545089);
+    }
+
+    #[test]
+    fn test_models_match_trying_every_hold() {
+        let models = [
+            AccelerationModel::Linear,
+            AccelerationModel::Quadratic,
+            AccelerationModel::Capped(0),
+            AccelerationModel::Capped(4),
+            AccelerationModel::BatteryDrain(0),
+            AccelerationModel::BatteryDrain(1),
+            AccelerationModel::BatteryDrain(3),
+        ];
+        for model in models {
+            for time in 0..40 {
+                for record in (0..200).step_by(7) {
+                    assert_eq!(model.solve(race(time, record)), solve_by_trying(model, race(time, record)), "{} {} {}", model, time, record);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_model_distances() {
+        assert_eq!(AccelerationModel::Quadratic.distance(30, 20), 4000);
+        assert_eq!(AccelerationModel::Capped(5).distance(30, 20), 50);
+        // 4 + 3 + 2 + 1, then stopped
+        assert_eq!(AccelerationModel::BatteryDrain(1).distance(30, 4), 10);
+        // 10 + 7 + 4, then out of time
+        assert_eq!(AccelerationModel::BatteryDrain(3).distance(13, 10), 21);
+        assert_eq!(AccelerationModel::Quadratic.distance(u64::MAX, u64::MAX / 2), u128::MAX);
+    }
+
+    #[test]
+    fn test_unbeatable_record() {
+        let report = AccelerationModel::Capped(2).report(race(10, 100));
+        assert_eq!(report.window, None);
+        assert_eq!(report.ways_to_win(), 0);
+        assert_eq!(report.optimal_hold, 2);
+        assert_eq!(report.margin(), -84);
+    }
+
+    #[test]
+    fn test_parse_models() {
+        assert_eq!("capped:12".parse::<AccelerationModel>(), Ok(AccelerationModel::Capped(12)));
+        assert_eq!("battery:3".parse::<AccelerationModel>(), Ok(AccelerationModel::BatteryDrain(3)));
+        assert!("capped:fast".parse::<AccelerationModel>().is_err());
+        assert!("rocket".parse::<AccelerationModel>().is_err());
+        assert_eq!(AccelerationModel::BatteryDrain(3).to_string(), "battery:3");
+    }
+}